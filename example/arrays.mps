@@ -0,0 +1,25 @@
+Fn sieve(int limit) int[] {
+    bool[] composite;
+    for int i := 0; i <= limit; i += 1 {
+        composite.push(false);
+    }
+    int[] primes;
+    for int n := 2; n <= limit; n += 1 {
+        if !composite[n] {
+            primes.push(n);
+            for int multiple := n * n; multiple <= limit; multiple += n {
+                composite[multiple] := true;
+            }
+        }
+    }
+    return primes;
+}
+
+int[] primes := sieve(50);
+print primes;
+print "{primes.len()} primes, the largest is {primes[primes.len() - 1]}";
+
+int[] stack := [1, 2, 3];
+while stack.len() > 0 {
+    print stack.pop();
+}
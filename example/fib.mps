@@ -1,10 +1,10 @@
 int t1 := 1;
 int t2 := 1;
-for int i := 0; i < 20; i := i + 1 {
+for int i := 0; i < 20; i += 1 {
     int next := t1 + t2;
     t1 := t2;
     t2 := next;
-    print t1;
+    print "step {i}: {t1}";
 }
 
 if t1 > 6764 {
@@ -13,6 +13,6 @@ if t1 > 6764 {
 
 int i := 0;
 while i < 11 {
-    print 2 ^ i;
-    i := i + 1;
+    print 2 ** i;
+    i += 1;
 }
\ No newline at end of file
@@ -0,0 +1,27 @@
+/* Functions are declared at the top level,
+   parameters and return value are statically typed */
+Fn fib(int n) int {
+    if n < 2 {
+        return n;
+    }
+    return fib(n - 1) + fib(n - 2);
+}
+
+Fn max(float a, float b) float {
+    if a > b {
+        return a;
+    }
+    return b;
+}
+
+//The return type of functions returning unit can be omitted
+Fn report(int n) {
+    print fib(n);
+}
+
+int i := 0;
+while i < 10 {
+    report(i);
+    i += 1;
+}
+print max(1.5, 2.5 * 2.0);
@@ -0,0 +1,39 @@
+enum Shape {
+    Circle(float),
+    Rect(float, float),
+    Empty,
+}
+
+Fn area(Shape shape) float {
+    return match shape {
+        Shape::Circle(r) => 3.14 * r * r,
+        Shape::Rect(w, h) => w * h,
+        Shape::Empty => 0.0,
+    };
+}
+
+Fn describe(Shape shape) {
+    match shape {
+        Shape::Circle(r) => {
+            print "circle of radius {r}";
+        }
+        Shape::Rect(w, h) => {
+            if w == h {
+                print "square of side {w}";
+            } else {
+                print "rectangle of {w} by {h}";
+            }
+        }
+        Shape::Empty => {
+            print "nothing";
+        }
+    }
+}
+
+Shape shape := Shape::Circle(1.0);
+describe(shape);
+print area(shape);
+shape := Shape::Rect(2.0, 2.0);
+describe(shape);
+print area(shape);
+print Shape::Empty;
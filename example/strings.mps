@@ -0,0 +1,18 @@
+string name := "w\u{f6}rld";
+string greeting := "hello " + name;
+print greeting;
+print greeting.len();
+
+//Strings are indexed and sliced by character
+print greeting[6];
+print substring(greeting, 0, 5);
+
+if substring(greeting, 0, 5) == "hello" {
+    print "\"{greeting}\" starts with \"hello\"";
+}
+
+int total := parse_int("40") + 2;
+print "total: " + int_to_string(total);
+
+//Expressions between braces are printed inside the string
+print "{name} has {name.len()} characters, total / 2 = {total / 2}";
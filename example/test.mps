@@ -1,8 +1,8 @@
 bool a := true;
-if a & true | false {
+if a && true || false {
     int c := 11;
     c := 7;
-    while true | false {
+    while true || false {
         int z := c * 2;
     }
 }
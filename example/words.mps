@@ -0,0 +1,19 @@
+/* Counts the occurrences of each word, the map lists them in alphabetical order */
+Fn count(string[] words) map<string, int> {
+    map<string, int> counts;
+    for word in words {
+        if counts.contains(word) {
+            counts.insert(word, counts.get(word) + 1);
+        } else {
+            counts.insert(word, 1);
+        }
+    }
+    return counts;
+}
+
+map<string, int> counts := count(["the", "cat", "saw", "the", "dog", "and", "the", "cat"]);
+print counts;
+
+for (word, count) in counts {
+    print "{word}: {count}";
+}
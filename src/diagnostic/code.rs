@@ -0,0 +1,60 @@
+//Lexing
+pub const UNEXPECTED_CHARACTER: &str = "E0101";
+pub const LITERAL_OUT_OF_RANGE: &str = "E0103";
+pub const UNTERMINATED_COMMENT: &str = "E0104";
+pub const UNTERMINATED_STRING: &str = "E0105";
+pub const INVALID_ESCAPE: &str = "E0106";
+pub const INVALID_INTERPOLATION: &str = "E0107";
+pub const UNTERMINATED_INTERPOLATION: &str = "E0108";
+
+//Parsing
+pub const UNEXPECTED_TOKEN: &str = "E0201";
+pub const UNEXPECTED_EOF: &str = "E0202";
+pub const MALFORMED_EXPRESSION: &str = "E0203";
+pub const UNCLOSED_PARENTHESIS: &str = "E0204";
+pub const EMPTY_STRUCT: &str = "E0205";
+
+//Analysis
+pub const UNDECLARED_VARIABLE: &str = "E0301";
+pub const ALREADY_DECLARED: &str = "E0302";
+pub const MISMATCHED_TYPES: &str = "E0303";
+pub const INVALID_OPERANDS: &str = "E0304";
+pub const UNDECLARED_FUNCTION: &str = "E0305";
+pub const WRONG_ARGUMENT_COUNT: &str = "E0306";
+pub const NESTED_FUNCTION: &str = "E0307";
+pub const MISSING_RETURN: &str = "E0308";
+pub const RETURN_OUTSIDE_FUNCTION: &str = "E0309";
+pub const CONSTANT_OVERFLOW: &str = "E0310";
+pub const NOT_PRINTABLE: &str = "E0311";
+pub const OUTSIDE_LOOP: &str = "E0312";
+pub const UNDECLARED_LABEL: &str = "E0313";
+pub const UNKNOWN_TYPE: &str = "E0314";
+pub const UNKNOWN_FIELD: &str = "E0315";
+pub const NESTED_STRUCT: &str = "E0316";
+pub const MISSING_FIELD: &str = "E0317";
+pub const NESTED_ENUM: &str = "E0318";
+pub const UNKNOWN_VARIANT: &str = "E0319";
+pub const NON_EXHAUSTIVE_MATCH: &str = "E0320";
+pub const UNREACHABLE_ARM: &str = "E0321";
+pub const UNKNOWN_METHOD: &str = "E0322";
+pub const NOT_ASSIGNABLE: &str = "E0323";
+pub const UNHASHABLE_KEY: &str = "E0324";
+pub const NOT_ITERABLE: &str = "E0325";
+pub const ZERO_STEP: &str = "E0326";
+
+//Runtime
+pub const RUNTIME_FAILURE: &str = "E0401";
+pub const DIVISION_BY_ZERO: &str = "E0402";
+pub const NEGATIVE_EXPONENT: &str = "E0403";
+pub const INVALID_OPERATION: &str = "E0404";
+pub const UNDEFINED_SYMBOL: &str = "E0405";
+pub const STACK_OVERFLOW: &str = "E0406";
+pub const INTEGER_OVERFLOW: &str = "E0407";
+pub const INDEX_OUT_OF_BOUNDS: &str = "E0408";
+pub const INVALID_SHIFT: &str = "E0409";
+pub const MISSING_KEY: &str = "E0410";
+pub const INVALID_STEP: &str = "E0411";
+pub const UNASSIGNED_VARIABLE: &str = "E0412";
+
+//Warnings
+pub const DEPRECATED_SYNTAX: &str = "W0101";
@@ -0,0 +1,119 @@
+use std::fmt::{Display, Formatter, Result};
+
+pub use crate::diagnostic::render::Renderer;
+pub use crate::diagnostic::span::{Position, Span};
+
+pub mod code;
+pub mod render;
+pub mod span;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Phase {
+    Lexing,
+    Parsing,
+    Analysis,
+    Runtime,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Phase::Lexing => write!(f, "lexing"),
+            Phase::Parsing => write!(f, "parsing"),
+            Phase::Analysis => write!(f, "analyzing"),
+            Phase::Runtime => write!(f, "executing")
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning")
+        }
+    }
+}
+
+//Secondary span pointing at code related to the problem, like the declaration of a variable
+#[derive(Clone, PartialEq, Debug)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+//The span is missing for problems that do not come from the source, like the ones raised by the host
+#[derive(Clone, PartialEq, Debug)]
+pub struct Diagnostic {
+    pub phase: Phase,
+    pub code: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub span: Option<Span>,
+    //Shown under the primary span
+    pub label: Option<String>,
+    pub secondary: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(phase: Phase, code: &'static str, message: String, span: Option<Span>) -> Self {
+        Self {
+            phase,
+            code,
+            severity: Severity::Error,
+            message,
+            span,
+            label: None,
+            secondary: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    pub fn warning(phase: Phase, code: &'static str, message: String, span: Option<Span>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(phase, code, message, span)
+        }
+    }
+
+    pub fn with_label(mut self, label: String) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    pub fn with_secondary(mut self, span: Span, message: String) -> Self {
+        self.secondary.push(Label { span, message });
+        self
+    }
+
+    pub fn with_note(mut self, note: String) -> Self {
+        self.notes.push(note);
+        self
+    }
+
+    pub fn with_help(mut self, help: String) -> Self {
+        self.help.push(help);
+        self
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}[{}] while {}", self.severity, self.code, self.phase)?;
+        if let Some(span) = self.span {
+            write!(f, " at {}", span.start)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
@@ -0,0 +1,113 @@
+use crate::diagnostic::{Diagnostic, Severity, Span};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+
+const TAB_WIDTH: usize = 4;
+
+//Renders diagnostics in the style of rustc, quoting the lines of the source they point at
+pub struct Renderer<'a> {
+    source: &'a str,
+    file_name: &'a str,
+    color: bool,
+}
+
+struct Annotation<'a> {
+    span: Span,
+    message: Option<&'a str>,
+    primary: bool,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(source: &'a str, file_name: &'a str) -> Self {
+        Self {
+            source,
+            file_name,
+            color: false,
+        }
+    }
+
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let severity_color = match diagnostic.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW
+        };
+        let mut out = format!("{}{}[{}]{}{}: {}{}\n",
+                              self.paint(severity_color), diagnostic.severity, diagnostic.code, self.paint(RESET),
+                              self.paint(BOLD), diagnostic.message, self.paint(RESET));
+        let mut annotations: Vec<Annotation> = diagnostic.secondary.iter()
+            .map(|label| Annotation { span: label.span, message: Some(&label.message), primary: false })
+            .collect();
+        if let Some(span) = diagnostic.span {
+            annotations.push(Annotation { span, message: diagnostic.label.as_deref(), primary: true });
+        }
+        annotations.sort_by_key(|annotation| (annotation.span.start.line, annotation.span.start.column));
+        let width = annotations.iter().map(|annotation| annotation.span.start.line).max().unwrap_or(0).to_string().len();
+        let gutter = format!("{}{} |{}", self.paint(BLUE), " ".repeat(width), self.paint(RESET));
+        if let Some(span) = diagnostic.span {
+            out.push_str(&format!("{}{}-->{} {}:{}\n", " ".repeat(width), self.paint(BLUE), self.paint(RESET),
+                                  self.file_name, span.start));
+        }
+        if !annotations.is_empty() {
+            out.push_str(&format!("{}\n", gutter));
+        }
+        let mut previous_line: Option<u32> = None;
+        for annotation in annotations.iter() {
+            let line_number = annotation.span.start.line;
+            let line = self.source.lines().nth(line_number as usize - 1).unwrap_or("");
+            if previous_line != Some(line_number) {
+                if previous_line.is_some_and(|previous| line_number > previous + 1) {
+                    out.push_str(&format!("{}...{}\n", self.paint(BLUE), self.paint(RESET)));
+                }
+                out.push_str(&format!("{}{:>width$} |{} {}\n", self.paint(BLUE), line_number, self.paint(RESET),
+                                      expand_tabs(line), width = width));
+            }
+            previous_line = Some(line_number);
+            let (padding, length) = underline(line, annotation.span);
+            let (marker, color) = if annotation.primary { ("^", severity_color) } else { ("-", BLUE) };
+            out.push_str(&format!("{} {}{}{}", gutter, " ".repeat(padding), self.paint(color), marker.repeat(length)));
+            if let Some(message) = annotation.message {
+                out.push_str(&format!(" {}", message));
+            }
+            out.push_str(&format!("{}\n", self.paint(RESET)));
+        }
+        let has_footer = !diagnostic.notes.is_empty() || !diagnostic.help.is_empty();
+        if !annotations.is_empty() && has_footer {
+            out.push_str(&format!("{}\n", gutter));
+        }
+        for (kind, lines) in [("note", &diagnostic.notes), ("help", &diagnostic.help)] {
+            for line in lines.iter() {
+                out.push_str(&format!("{}{} ={} {}{}:{} {}\n", " ".repeat(width), self.paint(BLUE), self.paint(RESET),
+                                      self.paint(BOLD), kind, self.paint(RESET), line));
+            }
+        }
+        out
+    }
+
+    fn paint(&self, code: &'static str) -> &'static str {
+        if self.color { code } else { "" }
+    }
+}
+
+fn expand_tabs(line: &str) -> String {
+    line.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+//Width on screen of the text before the span and of the span itself, spans covering several lines
+//are underlined until the end of their first line
+fn underline(line: &str, span: Span) -> (usize, usize) {
+    let width = |c: char| if c == '\t' { TAB_WIDTH } else { 1 };
+    let start = span.start.column as usize - 1;
+    let end = if span.end.line == span.start.line { span.end.column as usize - 1 } else { line.chars().count() };
+    let padding = line.chars().take(start).map(width).sum();
+    let length = line.chars().skip(start).take(end.saturating_sub(start)).map(width).sum();
+    (padding, usize::max(length, 1))
+}
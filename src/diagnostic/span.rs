@@ -0,0 +1,48 @@
+use std::fmt::{Display, Formatter, Result};
+
+//Lines and columns start from 1, the offset is in bytes from the start of the source
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Position {
+    pub line: u32,
+    pub column: u32,
+    pub offset: usize,
+}
+
+impl Position {
+    pub fn start() -> Self {
+        Self {
+            line: 1,
+            column: 1,
+            offset: 0,
+        }
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+//The end position is exclusive
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Self {
+            start,
+            end,
+        }
+    }
+
+    //Smallest span covering both self and other
+    pub fn to(self, other: Span) -> Span {
+        let start = if other.start.offset < self.start.offset { other.start } else { self.start };
+        let end = if other.end.offset > self.end.offset { other.end } else { self.end };
+        Span::new(start, end)
+    }
+}
@@ -0,0 +1,183 @@
+use std::rc::Rc;
+
+use crate::diagnostic::{code, Diagnostic, Phase};
+use crate::execution::builtins;
+use crate::execution::interpreter::Interpreter;
+use crate::execution::value::Value;
+use crate::parsing::analyzer::StaticAnalyzer;
+use crate::parsing::ast::{AST, FunctionSignature, Type};
+use crate::parsing::lexer::Lexer;
+use crate::parsing::parser::Parser;
+use crate::parsing::token::Token;
+
+/// A program that went through the static analysis of the [`Engine`] that compiled it.
+#[derive(Clone)]
+pub struct Program {
+    ast: AST
+}
+
+/// Entry point for embedding the language.
+///
+/// The engine keeps the global variables and functions alive between programs, a program
+/// compiled by an engine can use whatever was declared by the programs compiled before it.
+/// Programs must therefore be run in the same order they were compiled.
+///
+/// Runtime errors never panic, they are returned as diagnostics with the trace of the calls that
+/// led to them. Calls and blocks in the scripts recurse on the native stack, a program using more
+/// than 6 MB of it is stopped with a stack overflow error, see [`Engine::set_stack_limit`].
+pub struct Engine {
+    analyzer: StaticAnalyzer,
+    interpreter: Interpreter,
+    warnings: Vec<Diagnostic>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        let mut engine = Self {
+            analyzer: StaticAnalyzer::new(),
+            interpreter: Interpreter::new(),
+            warnings: Vec::new(),
+        };
+        for (id, parameters, return_type, function) in builtins::functions() {
+            engine.analyzer.declare_function(id.clone(), FunctionSignature { parameters, return_type: return_type.clone() });
+            engine.interpreter.register_native(id, function, return_type);
+        }
+        engine
+    }
+
+    /// Changes the bytes of native stack a program can use before it is stopped with a stack overflow
+    /// error. The default of 6 MB fits in the 8 MB stack of a main thread, a host running the engine
+    /// on a thread with a smaller stack must lower it.
+    pub fn set_stack_limit(&mut self, bytes: usize) {
+        self.interpreter.set_stack_limit(bytes);
+    }
+
+    /// Lexes, parses and analyzes `source`, declarations are kept only if the whole program is valid.
+    ///
+    /// Every error of the failing phase is returned, in the order they appear in the source.
+    pub fn compile(&mut self, source: &str) -> Result<Program, Vec<Diagnostic>> {
+        let tokens = self.lex(source)?;
+        let ast = Parser::new(&tokens).parse()?;
+        let snapshot = self.analyzer.clone();
+        if let Err(errors) = self.analyzer.analyze(ast.clone()) {
+            self.analyzer = snapshot;
+            return Err(errors);
+        }
+        Ok(Program { ast })
+    }
+
+    pub fn run(&mut self, program: Program) -> Result<(), Diagnostic> {
+        self.interpreter.eval(program.ast).map_err(Diagnostic::from)
+    }
+
+    /// Compiles and runs `source`, if it fails at runtime its declarations are forgotten together with
+    /// the global variables it declared.
+    pub fn eval(&mut self, source: &str) -> Result<(), Vec<Diagnostic>> {
+        let snapshot = self.analyzer.clone();
+        let program = self.compile(source)?;
+        self.run(program).map_err(|error| {
+            self.analyzer = snapshot;
+            let analyzer = &self.analyzer;
+            self.interpreter.retain_globals(|id| analyzer.global_type(id.to_string()).is_some());
+            vec![error]
+        })
+    }
+
+    /// Evaluates a single expression, `source` may be terminated by ';'.
+    pub fn eval_expression(&mut self, source: &str) -> Result<Value, Vec<Diagnostic>> {
+        let tokens = self.lex(source)?;
+        let expr = Parser::new(&tokens).parse_single_expression().map_err(|error| vec![error])?;
+        self.analyzer.analyze_expression(expr.clone())?;
+        self.interpreter.eval_expression(expr).map_err(|error| vec![error.into()])
+    }
+
+    /// Statically computes the type of a single expression without evaluating it.
+    pub fn type_of(&self, source: &str) -> Result<Type, Vec<Diagnostic>> {
+        let tokens = Lexer::new(source).lex().map_err(|error| vec![error])?;
+        let expr = Parser::new(&tokens).parse_single_expression().map_err(|error| vec![error])?;
+        self.analyzer.analyze_expression(expr)
+    }
+
+    /// Exposes a Rust callback to the scripts, calls to it are type checked against `parameters`
+    /// and `return_type` like calls to functions declared in the scripts.
+    pub fn register_function<F>(&mut self, id: &str, parameters: Vec<Type>, return_type: Type,
+                                function: F) -> Result<(), Diagnostic>
+        where F: Fn(&[Value]) -> Result<Value, String> + 'static {
+        let signature = FunctionSignature { parameters, return_type: return_type.clone() };
+        if !self.analyzer.declare_function(id.to_string(), signature) {
+            return Err(Diagnostic::error(Phase::Analysis, code::ALREADY_DECLARED,
+                                         format!("Cannot declare function {} because it was already declared", id),
+                                         None));
+        }
+        self.interpreter.register_native(id.to_string(), Rc::new(function), return_type);
+        Ok(())
+    }
+
+    /// Declares a global variable of type `v_type`, or assigns it if it was already declared with the
+    /// same type. The structs and enums in the type must have been declared by a program, and the value
+    /// must have that type, down to every element of a collection, field of a struct and payload of a variant.
+    pub fn set_global(&mut self, id: &str, v_type: Type, value: Value) -> Result<(), Diagnostic> {
+        if !v_type.is_known() {
+            return Err(Diagnostic::error(Phase::Analysis, code::UNKNOWN_TYPE,
+                                         format!("Cannot declare variable {} with the incomplete type {}", id, v_type),
+                                         None));
+        }
+        if !self.analyzer.is_type_declared(&v_type) {
+            return Err(Diagnostic::error(Phase::Analysis, code::UNKNOWN_TYPE,
+                                         format!("Cannot declare variable {} with the undeclared type {}", id, v_type),
+                                         None));
+        }
+        if !self.analyzer.has_type(&value, &v_type) {
+            return Err(Diagnostic::error(Phase::Analysis, code::MISMATCHED_TYPES,
+                                         format!("Mismatched types variable {} was declared {} but assigned {}",
+                                                 id, v_type, value.value_type()),
+                                         None));
+        }
+        match self.analyzer.global_type(id.to_string()) {
+            None => {
+                self.analyzer.declare_global(id.to_string(), v_type);
+            }
+            Some(d_type) if d_type == v_type => (),
+            Some(d_type) => return Err(Diagnostic::error(Phase::Analysis, code::MISMATCHED_TYPES,
+                                                         format!("Mismatched types variable {} was declared {} but assigned {}",
+                                                                 id, d_type, v_type),
+                                                         None))
+        }
+        self.interpreter.declare_global(id.to_string(), value);
+        Ok(())
+    }
+
+    /// Warnings of the last source compiled or evaluated, they never prevent a program from running.
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    /// Current value of a global variable, None if it is not declared or not assigned yet.
+    pub fn get_global(&self, id: &str) -> Option<Value> {
+        self.interpreter.retrieve_global(id.to_string())
+    }
+
+    /// Every assigned global variable sorted by name, with its type and current value.
+    pub fn globals(&self) -> Vec<(String, Type, Value)> {
+        self.analyzer.global_variables().into_iter()
+            .filter_map(|(id, v_type)| {
+                let value = self.interpreter.retrieve_global(id.clone())?;
+                Some((id, v_type, value))
+            })
+            .collect()
+    }
+
+    //The warnings of a new source replace the ones of the previous source
+    fn lex(&mut self, source: &str) -> Result<Vec<Token>, Vec<Diagnostic>> {
+        let mut lexer = Lexer::new(source);
+        let tokens = lexer.lex().map_err(|error| vec![error]);
+        self.warnings = lexer.warnings();
+        tokens
+    }
+}
@@ -0,0 +1,73 @@
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use crate::execution::interpreter::NativeFunction;
+use crate::execution::value::Value;
+use crate::parsing::ast::Type;
+
+type IntegerOperation = fn(i32, i32) -> Result<i32, String>;
+
+//Integer arithmetic with an explicit behavior on overflow, the operators raise an error instead
+const INTEGER_OPERATIONS: [(&str, IntegerOperation); 8] = [
+    ("wrapping_add", |l, r| Ok(l.wrapping_add(r))),
+    ("wrapping_sub", |l, r| Ok(l.wrapping_sub(r))),
+    ("wrapping_mul", |l, r| Ok(l.wrapping_mul(r))),
+    ("wrapping_pow", |l, r| Ok(l.wrapping_pow(exponent(r)?))),
+    ("saturating_add", |l, r| Ok(l.saturating_add(r))),
+    ("saturating_sub", |l, r| Ok(l.saturating_sub(r))),
+    ("saturating_mul", |l, r| Ok(l.saturating_mul(r))),
+    ("saturating_pow", |l, r| Ok(l.saturating_pow(exponent(r)?))),
+];
+
+type Builtin = (String, Vec<Type>, Type, NativeFunction);
+
+//Functions available to every program, as id, parameters, return type and implementation
+pub fn functions() -> Vec<Builtin> {
+    let mut functions: Vec<Builtin> = INTEGER_OPERATIONS.iter()
+        .map(|&(id, operation)| {
+            let function: NativeFunction = Rc::new(move |args: &[Value]| {
+                operation(args[0].expect_int()?, args[1].expect_int()?).map(Value::IntegerValue)
+            });
+            (id.to_string(), vec![Type::Integer, Type::Integer], Type::Integer, function)
+        })
+        .collect();
+    functions.append(&mut string_functions());
+    functions
+}
+
+//Strings are sliced by character, not by byte
+fn string_functions() -> Vec<Builtin> {
+    vec![
+        builtin("substring", vec![Type::String, Type::Integer, Type::Integer], Type::String, |args| {
+            let string = args[0].expect_string()?;
+            let (from, to) = (args[1].expect_int()?, args[2].expect_int()?);
+            let length = string.chars().count();
+            match (usize::try_from(from), usize::try_from(to)) {
+                (Ok(from), Ok(to)) if from <= to && to <= length =>
+                    Ok(Value::StringValue(string.chars().skip(from).take(to - from).collect())),
+                _ => Err(format!("Range {}..{} is out of bounds for a string of length {}", from, to, length))
+            }
+        }),
+        builtin("int_to_string", vec![Type::Integer], Type::String,
+                |args| Ok(Value::StringValue(args[0].expect_int()?.to_string()))),
+        builtin("float_to_string", vec![Type::FloatingPoint], Type::String,
+                |args| Ok(Value::StringValue(args[0].expect_float()?.to_string()))),
+        builtin("parse_int", vec![Type::String], Type::Integer, |args| {
+            let string = args[0].expect_string()?;
+            string.trim().parse().map(Value::IntegerValue).map_err(|_| format!("'{}' is not a valid int", string))
+        }),
+        builtin("parse_float", vec![Type::String], Type::FloatingPoint, |args| {
+            let string = args[0].expect_string()?;
+            string.trim().parse().map(Value::FloatValue).map_err(|_| format!("'{}' is not a valid float", string))
+        }),
+    ]
+}
+
+fn builtin<F>(id: &str, parameters: Vec<Type>, return_type: Type, function: F) -> Builtin
+    where F: Fn(&[Value]) -> Result<Value, String> + 'static {
+    (id.to_string(), parameters, return_type, Rc::new(function))
+}
+
+fn exponent(exponent: i32) -> Result<u32, String> {
+    u32::try_from(exponent).map_err(|_| format!("Cannot raise an integer to the negative power {}", exponent))
+}
@@ -0,0 +1,71 @@
+use std::fmt::{Display, Formatter, Result};
+
+use crate::diagnostic::{Diagnostic, Phase, Span};
+
+//Frames shown in a trace before the remaining ones are summarized, deep recursions would flood the report
+const MAX_TRACE_NOTES: usize = 10;
+
+//Call to a user function that was in progress when the error was raised
+#[derive(Clone, PartialEq, Debug)]
+pub struct TraceFrame {
+    pub function: String,
+    pub call: Span,
+}
+
+//Failure of a running program. Values raise it without a location, the interpreter then attaches
+//the span of the failing expression and the calls that led to it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct RuntimeError {
+    pub code: &'static str,
+    pub message: String,
+    pub span: Option<Span>,
+    //Innermost call first
+    pub trace: Vec<TraceFrame>,
+}
+
+impl RuntimeError {
+    pub fn new(code: &'static str, message: String) -> Self {
+        Self {
+            code,
+            message,
+            span: None,
+            trace: Vec::new(),
+        }
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "error[{}]", self.code)?;
+        if let Some(span) = self.span {
+            write!(f, " at {}", span.start)?;
+        }
+        write!(f, ": {}", self.message)?;
+        for frame in self.trace.iter() {
+            write!(f, "\n    in {} called at {}", frame.function, frame.call.start)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+//Native functions fail with a message, the helpers of Value they use can then be called with `?`
+impl From<RuntimeError> for String {
+    fn from(error: RuntimeError) -> Self {
+        error.message
+    }
+}
+
+impl From<RuntimeError> for Diagnostic {
+    fn from(error: RuntimeError) -> Self {
+        let mut diagnostic = Diagnostic::error(Phase::Runtime, error.code, error.message, error.span);
+        for frame in error.trace.iter().take(MAX_TRACE_NOTES) {
+            diagnostic = diagnostic.with_note(format!("in `{}` called at {}", frame.function, frame.call.start));
+        }
+        if error.trace.len() > MAX_TRACE_NOTES {
+            diagnostic = diagnostic.with_note(format!("... and {} more calls", error.trace.len() - MAX_TRACE_NOTES));
+        }
+        diagnostic
+    }
+}
@@ -1,86 +1,652 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use crate::diagnostic::{code, Span};
+use crate::execution::error::{RuntimeError, TraceFrame};
 use crate::execution::memory::Memory;
-use crate::execution::value::Value;
-use crate::parsing::ast::{AST, Expression};
-use crate::parsing::token::Operator;
+use crate::execution::value::{Key, TypeDeclarations, Value};
+use crate::parsing::ast::{Accessor, AST, ASTKind, Expression, ExpressionKind, Iterable, MatchArm, Pattern, Type};
+use crate::parsing::token::{Operator, UnaryOperator};
+
+//Native stack a program can use by default before it is stopped, the rest of the 8 MB stack of a main
+//thread is left to the host and to the native functions
+pub const DEFAULT_STACK_LIMIT: usize = 6 * 1024 * 1024;
+
+//Rust callback registered by the host, it receives the already evaluated arguments
+pub type NativeFunction = Rc<dyn Fn(&[Value]) -> Result<Value, String>>;
+
+#[derive(Clone)]
+enum Function {
+    User(Vec<String>, AST),
+    Native(NativeFunction, Type),
+}
+
+//Tells the enclosing statements whether to keep going or to unwind
+enum Flow {
+    Normal,
+    Return(Value),
+    //Label of the targeted loop, the innermost one if None
+    Break(Option<String>),
+    Continue(Option<String>),
+}
 
 pub struct Interpreter {
-    memory: Memory
+    memory: Memory,
+    functions: HashMap<String, Function>,
+    //Fields of each struct, in declaration order
+    structs: HashMap<String, Vec<(Type, String)>>,
+    //Variants of each enum with the types of their payload, to check the values returned by native functions
+    enums: HashMap<String, Vec<(String, Vec<Type>)>>,
+    //Calls to user functions in progress, outermost first
+    call_stack: Vec<TraceFrame>,
+    //Bytes of native stack the program can use, from where its evaluation started
+    stack_limit: usize,
+    stack_base: usize,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
         Self {
-            memory: Memory::new()
+            memory: Memory::new(),
+            functions: HashMap::new(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            call_stack: Vec::new(),
+            stack_limit: DEFAULT_STACK_LIMIT,
+            stack_base: 0,
         }
     }
 
-    pub fn eval(&mut self, ast: AST) {
-        match ast {
-            AST::VarDeclaration(_, id) => self.memory.declare(id),
-            AST::VarDeclarationAndAssignment(_, id, expr) => self.memory.declare_assign(id, self.eval_expression(expr)),
-            AST::Assign(id, expr) => self.memory.assign(id, self.eval_expression(expr)),
-            AST::Print(expr) => {
-                let to_print = match self.eval_expression(expr) {
-                    Value::BooleanValue(val) => val.to_string(),
-                    Value::FloatValue(val) => format!("{}", val),
-                    Value::IntegerValue(val) => format!("{}", val),
-                    Value::Unit => "unit".to_string()
-                };
-                println!("{}", to_print);
-            }
-            AST::Block(nodes) => {
-                for node in nodes {
-                    self.eval(node)
+    //On failure the frames of the interrupted calls are dropped, the globals keep their last values
+    pub fn eval(&mut self, ast: AST) -> Result<(), RuntimeError> {
+        self.stack_base = stack_address();
+        self.hoist_functions(&ast);
+        self.execute(ast).map(|_| ()).inspect_err(|_| self.recover())
+    }
+
+    pub fn set_stack_limit(&mut self, bytes: usize) {
+        self.stack_limit = bytes;
+    }
+
+    pub fn register_native(&mut self, id: String, function: NativeFunction, return_type: Type) {
+        self.functions.insert(id, Function::Native(function, return_type));
+    }
+
+    pub fn declare_global(&mut self, id: String, value: Value) {
+        self.memory.declare_global(id, value);
+    }
+
+    pub fn retrieve_global(&self, id: String) -> Option<Value> {
+        self.memory.retrieve_global(id)
+    }
+
+    //Drops the globals for which `keep` is false, like the ones of a program whose declarations are undone
+    pub fn retain_globals(&mut self, keep: impl FnMut(&str) -> bool) {
+        self.memory.retain_globals(keep);
+    }
+
+    pub fn eval_expression(&mut self, expr: Expression) -> Result<Value, RuntimeError> {
+        self.stack_base = stack_address();
+        self.evaluate(expr).inspect_err(|_| self.recover())
+    }
+
+    //Like in the analyzer, the functions of a program can be called before their declaration
+    fn hoist_functions(&mut self, ast: &AST) {
+        if let ASTKind::Block(nodes) = &ast.kind {
+            for node in nodes {
+                if let ASTKind::FunctionDeclaration(id, parameters, _, body) = &node.kind {
+                    let parameters = parameters.iter().map(|(_, parameter)| parameter.clone()).collect();
+                    self.functions.insert(id.clone(), Function::User(parameters, (**body).clone()));
                 }
             }
-            AST::IfStatement(cond, then_clause) => {
-                if self.eval_expression(cond).expect_bool() {
-                    self.memory.create_frame();
-                    self.eval(*then_clause);
+        }
+    }
+
+    fn recover(&mut self) {
+        self.memory.unwind_to_global();
+        self.call_stack.clear();
+    }
+
+    //Statements and expressions recurse through execute and evaluate, which only dispatch to the methods
+    //below: their frames are then small enough for deep recursions in the scripts, even in debug builds
+    fn execute(&mut self, ast: AST) -> Result<Flow, RuntimeError> {
+        let span = ast.span;
+        self.check_stack(span)?;
+        match ast.kind {
+            ASTKind::VarDeclaration(d_type, id) => self.declare(d_type, id),
+            ASTKind::VarDeclarationAndAssignment(_, id, expr) => self.declare_assign(id, expr)?,
+            ASTKind::Assign(id, expr) => self.assign(id, expr)?,
+            ASTKind::CompoundAssign(id, op, expr) => self.compound_assign(id, op, expr, span)?,
+            ASTKind::MemberAssign(id, path, op, expr) => self.member_assign(id, path, op, expr, span)?,
+            ASTKind::Print(expr) => self.print(expr)?,
+            ASTKind::Block(nodes) => return self.execute_block(nodes),
+            ASTKind::IfStatement(cond, then_clause, else_clause) => return self.execute_if(cond, then_clause, else_clause),
+            ASTKind::WhileStatement(label, cond, body) => return self.execute_while(label, cond, body),
+            ASTKind::ForStatement(label, dec, cond, inc, body) => return self.execute_for(label, *dec, cond, inc, body),
+            ASTKind::ForInStatement(label, bindings, iterable, body) =>
+                return self.execute_for_in(label, bindings, iterable, body, span),
+            ASTKind::FunctionDeclaration(id, parameters, _, body) => self.declare_function(id, parameters, *body),
+            ASTKind::StructDeclaration(id, fields) => {
+                self.structs.insert(id, fields);
+            }
+            ASTKind::EnumDeclaration(id, variants) => {
+                self.enums.insert(id, variants);
+            }
+            ASTKind::Match(value, arms) => return self.execute_match(value, arms, span),
+            ASTKind::Return(Some(expr)) => return self.evaluate(expr).map(Flow::Return),
+            ASTKind::Return(None) => return Ok(Flow::Return(Value::Unit)),
+            ASTKind::Break(label) => return Ok(Flow::Break(label)),
+            ASTKind::Continue(label) => return Ok(Flow::Continue(label)),
+            ASTKind::ExpressionStatement(expr) => return self.evaluate(expr).map(|_| Flow::Normal)
+        }
+        Ok(Flow::Normal)
+    }
+
+    //Arrays and maps start empty, the other variables must be assigned before they are used
+    fn declare(&mut self, d_type: Type, id: String) {
+        match d_type {
+            Type::Array(_) => self.memory.declare_assign(id, Value::ArrayValue(Vec::new())),
+            Type::Map(_, _) => self.memory.declare_assign(id, Value::MapValue(BTreeMap::new())),
+            _ => self.memory.declare(id)
+        }
+    }
+
+    fn declare_assign(&mut self, id: String, expr: Expression) -> Result<(), RuntimeError> {
+        let value = self.evaluate(expr)?;
+        self.memory.declare_assign(id, value);
+        Ok(())
+    }
+
+    fn assign(&mut self, id: String, expr: Expression) -> Result<(), RuntimeError> {
+        let value = self.evaluate(expr)?;
+        self.memory.assign(id, value);
+        Ok(())
+    }
+
+    fn print(&mut self, expr: Expression) -> Result<(), RuntimeError> {
+        println!("{}", self.evaluate(expr)?.printable());
+        Ok(())
+    }
+
+    fn declare_function(&mut self, id: String, parameters: Vec<(Type, String)>, body: AST) {
+        let parameters = parameters.into_iter().map(|(_, parameter)| parameter).collect();
+        self.functions.insert(id, Function::User(parameters, body));
+    }
+
+    fn compound_assign(&mut self, id: String, op: Operator, expr: Expression, span: Span) -> Result<(), RuntimeError> {
+        let current = self.variable(&id, span)?.clone();
+        let value = self.evaluate(expr)?;
+        let result = current.binary_operation(op, value).map_err(|error| self.locate(error, span))?;
+        self.memory.assign(id, result);
+        Ok(())
+    }
+
+    fn member_assign(&mut self, id: String, path: Vec<Accessor>, op: Option<Operator>, expr: Expression,
+                     span: Span) -> Result<(), RuntimeError> {
+        let value = self.evaluate(expr)?;
+        self.update(id, path, span, |member| {
+            *member = match op {
+                Some(op) => member.clone().binary_operation(op, value)?,
+                None => value
+            };
+            Ok(())
+        })
+    }
+
+    fn execute_block(&mut self, nodes: Vec<AST>) -> Result<Flow, RuntimeError> {
+        for node in nodes {
+            let flow = self.execute(node)?;
+            if !matches!(flow, Flow::Normal) {
+                return Ok(flow);
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn execute_if(&mut self, cond: Expression, then_clause: Box<AST>, else_clause: Option<Box<AST>>) -> Result<Flow, RuntimeError> {
+        let branch = if self.condition(cond)? { Some(then_clause) } else { else_clause };
+        match branch {
+            Some(branch) => {
+                self.memory.create_frame();
+                let flow = self.execute(*branch)?;
+                self.memory.remove_frame();
+                Ok(flow)
+            }
+            None => Ok(Flow::Normal)
+        }
+    }
+
+    fn execute_while(&mut self, label: Option<String>, cond: Expression, body: Box<AST>) -> Result<Flow, RuntimeError> {
+        self.memory.create_frame();
+        while self.condition(cond.clone())? {
+            match self.execute(*body.clone())? {
+                Flow::Normal => (),
+                Flow::Continue(target) if targets(&label, &target) => (),
+                Flow::Break(target) if targets(&label, &target) => break,
+                flow => {
                     self.memory.remove_frame();
+                    return Ok(flow);
                 }
             }
-            AST::WhileStatement(cond, body) => {
-                self.memory.create_frame();
-                while self.eval_expression(cond.clone()).expect_bool() {
-                    self.eval(*body.clone())
+        }
+        self.memory.remove_frame();
+        Ok(Flow::Normal)
+    }
+
+    fn execute_for(&mut self, label: Option<String>, dec: AST, cond: Expression, inc: Box<AST>,
+                   body: Box<AST>) -> Result<Flow, RuntimeError> {
+        self.memory.create_frame();
+        self.execute(dec)?;
+        while self.condition(cond.clone())? {
+            match self.execute(*body.clone())? {
+                Flow::Normal => (),
+                //The increment still runs, it is what moves the loop forward
+                Flow::Continue(target) if targets(&label, &target) => (),
+                Flow::Break(target) if targets(&label, &target) => break,
+                flow => {
+                    self.memory.remove_frame();
+                    return Ok(flow);
                 }
-                self.memory.remove_frame();
             }
-            AST::ForStatement(dec, cond, inc, body) => {
-                self.memory.create_frame();
-                self.eval(*dec);
-                while self.eval_expression(cond.clone()).expect_bool() {
-                    self.eval(*body.clone());
-                    self.eval(*inc.clone());
+            self.execute(*inc.clone())?;
+        }
+        self.memory.remove_frame();
+        Ok(Flow::Normal)
+    }
+
+    fn execute_for_in(&mut self, label: Option<String>, bindings: Vec<Option<String>>, iterable: Iterable, body: Box<AST>,
+                      span: Span) -> Result<Flow, RuntimeError> {
+        for values in self.iterate(iterable, span)? {
+            //Each iteration gets its own frame, the bound names do not outlive it
+            self.memory.create_frame();
+            for (binding, value) in bindings.iter().zip(values) {
+                if let Some(binding) = binding {
+                    self.memory.declare_assign(binding.clone(), value);
                 }
-                self.memory.remove_frame();
+            }
+            let flow = self.execute(*body.clone());
+            self.memory.remove_frame();
+            match flow? {
+                Flow::Normal => (),
+                Flow::Continue(target) if targets(&label, &target) => (),
+                Flow::Break(target) if targets(&label, &target) => break,
+                flow => return Ok(flow)
             }
         }
+        Ok(Flow::Normal)
+    }
+
+    fn execute_match(&mut self, value: Expression, arms: Vec<MatchArm<AST>>, span: Span) -> Result<Flow, RuntimeError> {
+        let value = self.evaluate(value)?;
+        let (bindings, body) = self.select_arm(arms, &value, span)?;
+        self.memory.create_frame();
+        for (binding, value) in bindings {
+            self.memory.declare_assign(binding, value);
+        }
+        let flow = self.execute(body)?;
+        self.memory.remove_frame();
+        Ok(flow)
+    }
+
+    fn evaluate(&mut self, expr: Expression) -> Result<Value, RuntimeError> {
+        let span = expr.span;
+        match expr.kind {
+            ExpressionKind::IntegerLiteral(val) => Ok(Value::IntegerValue(val)),
+            ExpressionKind::FloatLiteral(val) => Ok(Value::FloatValue(val)),
+            ExpressionKind::BooleanLiteral(val) => Ok(Value::BooleanValue(val)),
+            ExpressionKind::StringLiteral(val) => Ok(Value::StringValue(val)),
+            ExpressionKind::Interpolation(parts) => self.interpolate(parts),
+            ExpressionKind::Variable(id) => self.variable(&id, span).cloned(),
+            ExpressionKind::UnaryOperation(op, operand) => self.unary_operation(op, *operand, span),
+            ExpressionKind::BinaryOperation(left, op, right) => self.binary_operation(*left, op, *right, span),
+            ExpressionKind::FunctionCall(id, arguments) => self.call_function(id, arguments, span),
+            ExpressionKind::StructLiteral(id, fields) => self.struct_literal(id, fields),
+            ExpressionKind::FieldAccess(target, field) => self.field_access(*target, field, span),
+            ExpressionKind::EnumVariant(id, variant, values) => self.evaluate_all(values).map(|payload| Value::EnumValue(id, variant, payload)),
+            ExpressionKind::Match(value, arms) => self.evaluate_match(*value, arms, span),
+            ExpressionKind::Index(target, index) => self.index(*target, *index, span),
+            ExpressionKind::ArrayLiteral(elements) => self.evaluate_all(elements).map(Value::ArrayValue),
+            ExpressionKind::MapLiteral(entries) => self.map_literal(entries, span),
+            ExpressionKind::MethodCall(target, method, arguments) => self.method_call(*target, method, arguments, span)
+        }
+    }
+
+    //A variable declared without a value has none until it is assigned, no operation can read it
+    fn variable(&self, id: &str, span: Span) -> Result<&Value, RuntimeError> {
+        match self.memory.retrieve(id) {
+            Some(Some(value)) => Ok(value),
+            Some(None) => Err(self.error(code::UNASSIGNED_VARIABLE,
+                                         format!("Variable {} is read before being assigned", id),
+                                         span)),
+            None => Err(self.error(code::UNDEFINED_SYMBOL, format!("Unable to find variable {} in memory", id), span))
+        }
+    }
+
+    fn unary_operation(&mut self, op: UnaryOperator, operand: Expression, span: Span) -> Result<Value, RuntimeError> {
+        let operand = self.evaluate(operand)?;
+        operand.unary_operation(op).map_err(|error| self.locate(error, span))
     }
 
-    fn eval_expression(&self, expr: Expression) -> Value {
-        match expr {
-            Expression::IntegerLiteral(val) => Value::IntegerValue(val),
-            Expression::FloatLiteral(val) => Value::FloatValue(val),
-            Expression::BooleanLiteral(val) => Value::BooleanValue(val),
-            Expression::Variable(id) => self.memory.retrieve_val(id),
-            Expression::BinaryOperation(left, op, right) => {
-                let left = self.eval_expression(*left);
-                let right = self.eval_expression(*right);
-                match op {
-                    Operator::Add => left + right,
-                    Operator::Sub => left - right,
-                    Operator::Mul => left * right,
-                    Operator::Div => left / right,
-                    Operator::Pow => left ^ right,
-                    Operator::And => left & right,
-                    Operator::Or => left | right,
-                    Operator::Eq => Value::BooleanValue(left == right),
-                    Operator::Gt => Value::BooleanValue(left > right),
-                    Operator::Lt => Value::BooleanValue(left < right),
+    fn field_access(&mut self, target: Expression, field: String, span: Span) -> Result<Value, RuntimeError> {
+        match target.place() {
+            Some((id, mut path)) => {
+                path.push(Accessor::Field(field));
+                self.read(id, path, span)
+            }
+            None => {
+                let target = self.evaluate(target)?;
+                target.field(&field).cloned().map_err(|error| self.locate(error, span))
+            }
+        }
+    }
+
+    fn index(&mut self, target: Expression, index: Expression, span: Span) -> Result<Value, RuntimeError> {
+        match target.place() {
+            Some((id, mut path)) => {
+                path.push(Accessor::Index(index));
+                self.read(id, path, span)
+            }
+            None => {
+                let target = self.evaluate(target)?;
+                let index = self.evaluate(index)?;
+                target.index(&index).map(Cow::into_owned).map_err(|error| self.locate(error, span))
+            }
+        }
+    }
+
+    fn evaluate_all(&mut self, exprs: Vec<Expression>) -> Result<Vec<Value>, RuntimeError> {
+        let mut values = Vec::with_capacity(exprs.len());
+        for expr in exprs {
+            values.push(self.evaluate(expr)?);
+        }
+        Ok(values)
+    }
+
+    fn interpolate(&mut self, parts: Vec<Expression>) -> Result<Value, RuntimeError> {
+        let mut string = String::new();
+        for part in parts {
+            string.push_str(&self.evaluate(part)?.printable());
+        }
+        Ok(Value::StringValue(string))
+    }
+
+    fn binary_operation(&mut self, left: Expression, op: Operator, right: Expression, span: Span) -> Result<Value, RuntimeError> {
+        let left = self.evaluate(left)?;
+        //false && ... and true || ... are decided without the right operand
+        if matches!(op, Operator::And | Operator::Or) && left == Value::BooleanValue(op == Operator::Or) {
+            return Ok(left);
+        }
+        let right = self.evaluate(right)?;
+        left.binary_operation(op, right).map_err(|error| self.locate(error, span))
+    }
+
+    fn call_function(&mut self, id: String, arguments: Vec<Expression>, span: Span) -> Result<Value, RuntimeError> {
+        let arguments = self.evaluate_all(arguments)?;
+        match self.functions.get(&id).cloned() {
+            Some(Function::User(parameters, body)) => self.call(id, parameters, body, arguments, span),
+            Some(Function::Native(function, return_type)) => match function(&arguments) {
+                Ok(value) if value.has_type(&return_type, self) => Ok(value),
+                Ok(value) => Err(self.error(code::RUNTIME_FAILURE,
+                                            format!("Native function {} was declared returning {} but returned {}",
+                                                    id, return_type, value.value_type()),
+                                            span)),
+                Err(error) => Err(self.error(code::RUNTIME_FAILURE, format!("Native function {} failed: {}", id, error), span))
+            },
+            None => Err(self.error(code::UNDEFINED_SYMBOL, format!("Unable to find function {}", id), span))
+        }
+    }
+
+    fn struct_literal(&mut self, id: String, fields: Vec<(String, Expression)>) -> Result<Value, RuntimeError> {
+        let mut values = Vec::new();
+        for (field, expr) in fields {
+            values.push((field, self.evaluate(expr)?));
+        }
+        //Written in any order, stored in declaration order
+        if let Some(declared) = self.structs.get(&id) {
+            values.sort_by_key(|(field, _)| declared.iter().position(|(_, name)| name == field));
+        }
+        Ok(Value::StructValue(id, values))
+    }
+
+    fn evaluate_match(&mut self, value: Expression, arms: Vec<MatchArm<Expression>>, span: Span) -> Result<Value, RuntimeError> {
+        let value = self.evaluate(value)?;
+        let (bindings, body) = self.select_arm(arms, &value, span)?;
+        self.memory.create_frame();
+        for (binding, value) in bindings {
+            self.memory.declare_assign(binding, value);
+        }
+        let result = self.evaluate(body)?;
+        self.memory.remove_frame();
+        Ok(result)
+    }
+
+    //A repeated key keeps its last value
+    fn map_literal(&mut self, entries: Vec<(Expression, Expression)>, span: Span) -> Result<Value, RuntimeError> {
+        let mut values = BTreeMap::new();
+        for (key, value) in entries {
+            let key = Key::try_from(self.evaluate(key)?).map_err(|error| self.locate(error, span))?;
+            values.insert(key, self.evaluate(value)?);
+        }
+        Ok(Value::MapValue(values))
+    }
+
+    fn method_call(&mut self, target: Expression, method: String, arguments: Vec<Expression>,
+                   span: Span) -> Result<Value, RuntimeError> {
+        let arguments = self.evaluate_all(arguments)?;
+        match target.place() {
+            //The method may change its target, it is applied to the variable itself
+            Some((id, path)) => self.update(id, path, span, |member| member.call_method(&method, arguments)),
+            None => {
+                let mut target = self.evaluate(target)?;
+                target.call_method(&method, arguments).map_err(|error| self.locate(error, span))
+            }
+        }
+    }
+
+    fn call(&mut self, id: String, parameters: Vec<String>, body: AST, arguments: Vec<Value>,
+            span: Span) -> Result<Value, RuntimeError> {
+        self.check_stack(span)?;
+        self.call_stack.push(TraceFrame { function: id, call: span });
+        self.memory.create_call_frame();
+        for (parameter, value) in parameters.into_iter().zip(arguments) {
+            self.memory.declare_assign(parameter, value);
+        }
+        let result = match self.execute(body)? {
+            Flow::Return(value) => value,
+            //The analyzer keeps break and continue inside loops, they cannot leave the function
+            Flow::Normal | Flow::Break(_) | Flow::Continue(_) => Value::Unit
+        };
+        self.memory.remove_call_frame();
+        self.call_stack.pop();
+        Ok(result)
+    }
+
+    //Copies a part of a variable, like `p.x` or `xs[0]`, without copying the rest of the variable
+    fn read(&mut self, id: String, path: Vec<Accessor>, span: Span) -> Result<Value, RuntimeError> {
+        let indices = self.evaluate_indices(&path)?;
+        let mut indices = indices.iter();
+        let root = self.variable(&id, span)?;
+        path.iter()
+            .try_fold(Cow::Borrowed(root), |member, accessor| match accessor {
+                Accessor::Field(field) => step(member, |value| value.field(field).map(Cow::Borrowed)),
+                Accessor::Index(_) => step(member, |value| value.index(indices.next().unwrap()))
+            })
+            .map(Cow::into_owned)
+            .map_err(|error| self.locate(error, span))
+    }
+
+    //Applies `change` to a part of a variable, like `p.x` or `xs[0]`, in place
+    fn update<R>(&mut self, id: String, path: Vec<Accessor>, span: Span,
+                 change: impl FnOnce(&mut Value) -> Result<R, RuntimeError>) -> Result<R, RuntimeError> {
+        let indices = self.evaluate_indices(&path)?;
+        let mut indices = indices.iter();
+        //Checked first, the errors cannot be built while the variable is borrowed
+        self.variable(&id, span)?;
+        let result = path.iter()
+            .try_fold(self.memory.retrieve_mut(&id).unwrap(), |member, accessor| match accessor {
+                Accessor::Field(field) => member.field_mut(field),
+                Accessor::Index(_) => member.index_mut(indices.next().unwrap())
+            })
+            .and_then(change);
+        result.map_err(|error| self.locate(error, span))
+    }
+
+    //The indices along a path are evaluated before the variable is borrowed
+    fn evaluate_indices(&mut self, path: &[Accessor]) -> Result<Vec<Value>, RuntimeError> {
+        let mut indices = Vec::new();
+        for accessor in path {
+            if let Accessor::Index(index) = accessor {
+                indices.push(self.evaluate(index.clone())?);
+            }
+        }
+        Ok(indices)
+    }
+
+    //Body of the first arm accepting the value, with the variables bound by its pattern
+    fn select_arm<T>(&self, arms: Vec<MatchArm<T>>, value: &Value, span: Span) -> Result<(Vec<(String, Value)>, T), RuntimeError> {
+        for arm in arms {
+            if let Some(bindings) = bind(&arm.pattern, value) {
+                return Ok((bindings, arm.body));
+            }
+        }
+        Err(self.error(code::INVALID_OPERATION,
+                       format!("No arm of the match accepts the value {}", value.clone().printable()),
+                       span))
+    }
+
+    //Values bound by each iteration of a for loop, the bounds and step of a range are evaluated once
+    fn iterate(&mut self, iterable: Iterable, span: Span) -> Result<Box<dyn Iterator<Item = Vec<Value>>>, RuntimeError> {
+        match iterable {
+            Iterable::Range(start, end, inclusive, step) => {
+                let start = self.integer(*start)?;
+                let end = self.integer(*end)?;
+                let step = match step {
+                    Some(step) => self.integer(*step)?,
+                    None => 1
+                };
+                if step == 0 {
+                    return Err(self.error(code::INVALID_STEP, "The step of a range cannot be 0".to_string(), span));
                 }
+                Ok(Box::new(range(start, end, inclusive, step).map(|value| vec![Value::IntegerValue(value)])))
+            }
+            Iterable::Collection(collection) => match self.evaluate(collection)? {
+                Value::StringValue(val) => Ok(Box::new(val.chars()
+                    .map(|character| vec![Value::StringValue(character.to_string())])
+                    .collect::<Vec<_>>()
+                    .into_iter())),
+                Value::ArrayValue(elements) => Ok(Box::new(elements.into_iter().map(|element| vec![element]))),
+                Value::MapValue(entries) => Ok(Box::new(entries.into_iter().map(|(key, value)| vec![Value::from(key), value]))),
+                value => Err(self.error(code::INVALID_OPERATION, format!("Cannot iterate over a value of type {}", value), span))
             }
         }
     }
+
+    //Calls and blocks recurse on the native stack, a program nesting too many of them is stopped before
+    //it overflows the stack of the host
+    fn check_stack(&self, span: Span) -> Result<(), RuntimeError> {
+        if self.stack_base.saturating_sub(stack_address()) > self.stack_limit {
+            return Err(self.error(code::STACK_OVERFLOW,
+                                  format!("Stack overflow, the program used more than {} KB of native stack",
+                                          self.stack_limit / 1024),
+                                  span));
+        }
+        Ok(())
+    }
+
+    fn condition(&mut self, expr: Expression) -> Result<bool, RuntimeError> {
+        let span = expr.span;
+        match self.evaluate(expr)? {
+            Value::BooleanValue(val) => Ok(val),
+            value => Err(self.error(code::INVALID_OPERATION,
+                                    format!("Expected a boolean condition but found a value of type {}", value),
+                                    span))
+        }
+    }
+
+    fn integer(&mut self, expr: Expression) -> Result<i32, RuntimeError> {
+        let span = expr.span;
+        let value = self.evaluate(expr)?;
+        value.expect_int().map_err(|error| self.locate(error, span))
+    }
+
+    fn error(&self, code: &'static str, message: String, span: Span) -> RuntimeError {
+        self.locate(RuntimeError::new(code, message), span)
+    }
+
+    //Attaches the failing expression and the calls in progress to an error raised by a value
+    fn locate(&self, mut error: RuntimeError, span: Span) -> RuntimeError {
+        error.span = Some(span);
+        error.trace = self.call_stack.iter().rev().cloned().collect();
+        error
+    }
+}
+
+impl TypeDeclarations for Interpreter {
+    fn struct_fields(&self, id: &str) -> Option<&[(Type, String)]> {
+        self.structs.get(id).map(Vec::as_slice)
+    }
+
+    fn enum_variants(&self, id: &str) -> Option<&[(String, Vec<Type>)]> {
+        self.enums.get(id).map(Vec::as_slice)
+    }
+}
+
+//Applies `access` to a part of a variable, keeping it borrowed unless it was built anew
+fn step<'a>(member: Cow<'a, Value>,
+            access: impl FnOnce(&Value) -> Result<Cow<'_, Value>, RuntimeError>) -> Result<Cow<'a, Value>, RuntimeError> {
+    match member {
+        Cow::Borrowed(value) => access(value),
+        Cow::Owned(value) => access(&value).map(|part| Cow::Owned(part.into_owned()))
+    }
+}
+
+//Approximated by the address of a local variable, the stack grows towards lower addresses
+fn stack_address() -> usize {
+    let marker = 0u8;
+    &marker as *const u8 as usize
+}
+
+//Whether a break or continue aimed at `target` is meant for the loop labeled `label`
+fn targets(label: &Option<String>, target: &Option<String>) -> bool {
+    target.is_none() || target == label
+}
+
+//Counts from start towards end by step, which may be negative. Stops instead of overflowing.
+fn range(start: i32, end: i32, inclusive: bool, step: i32) -> impl Iterator<Item = i32> {
+    let mut next = Some(start);
+    std::iter::from_fn(move || {
+        let current = next?;
+        let within = match (step > 0, inclusive) {
+            (true, false) => current < end,
+            (true, true) => current <= end,
+            (false, false) => current > end,
+            (false, true) => current >= end
+        };
+        if !within {
+            return None;
+        }
+        next = current.checked_add(step);
+        Some(current)
+    })
+}
+
+//Variables bound by the pattern if it accepts the value, None otherwise
+fn bind(pattern: &Pattern, value: &Value) -> Option<Vec<(String, Value)>> {
+    match (pattern, value) {
+        (Pattern::CatchAll(binding), value) => Some(binding.iter().map(|binding| (binding.clone(), value.clone())).collect()),
+        (Pattern::Variant(id, variant, bindings), Value::EnumValue(value_id, value_variant, payload))
+        if id == value_id && variant == value_variant =>
+            Some(bindings.iter().zip(payload)
+                .filter_map(|(binding, value)| binding.clone().map(|binding| (binding, value.clone())))
+                .collect()),
+        _ => None
+    }
 }
@@ -2,53 +2,98 @@ use std::collections::HashMap;
 
 use crate::execution::value::Value;
 
+//A variable declared without a value is None until it is assigned
 pub struct Memory {
-    memory_frame: Vec<HashMap<String, Value>>
+    call_frames: Vec<Vec<HashMap<String, Option<Value>>>>
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Memory {
     pub fn new() -> Self {
         Self {
-            memory_frame: vec![HashMap::new()]
+            call_frames: vec![vec![HashMap::new()]]
         }
     }
 
     pub fn create_frame(&mut self) {
-        self.memory_frame.push(HashMap::new());
+        self.current_call_frame().push(HashMap::new());
     }
 
     pub fn remove_frame(&mut self) {
-        self.memory_frame.pop();
+        self.current_call_frame().pop();
+    }
+
+    //A call frame only sees its own scopes and the global one
+    pub fn create_call_frame(&mut self) {
+        self.call_frames.push(vec![HashMap::new()]);
+    }
+
+    pub fn remove_call_frame(&mut self) {
+        self.call_frames.pop();
+    }
+
+    pub fn unwind_to_global(&mut self) {
+        self.call_frames.truncate(1);
+        self.call_frames[0].truncate(1);
     }
 
     pub fn declare(&mut self, key: String) {
-        let mut current_frame = self.memory_frame.pop().unwrap();
-        current_frame.insert(key, Value::Unit);
-        self.memory_frame.push(current_frame);
+        self.current_call_frame().last_mut().unwrap().insert(key, None);
     }
 
     pub fn declare_assign(&mut self, key: String, value: Value) {
-        let mut current_frame = self.memory_frame.pop().unwrap();
-        current_frame.insert(key, value);
-        self.memory_frame.push(current_frame);
+        self.current_call_frame().last_mut().unwrap().insert(key, Some(value));
     }
 
     pub fn assign(&mut self, key: String, value: Value) {
-        for frame in self.memory_frame.iter_mut().rev() {
-            if frame.contains_key(&key) {
-                frame.insert(key.to_string(), value);
-                break;
+        for frame in self.call_frames.last_mut().unwrap().iter_mut().rev() {
+            if let Some(slot) = frame.get_mut(&key) {
+                *slot = Some(value);
+                return;
             }
         }
+        self.call_frames[0][0].insert(key, Some(value));
+    }
+
+    pub fn declare_global(&mut self, key: String, value: Value) {
+        self.call_frames[0][0].insert(key, Some(value));
+    }
+
+    pub fn retain_globals(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.call_frames[0][0].retain(|key, _| keep(key));
+    }
+
+    //None for a global that is not declared or not assigned yet
+    pub fn retrieve_global(&self, key: String) -> Option<Value> {
+        self.call_frames[0][0].get(&key).cloned().flatten()
     }
 
-    pub fn retrieve_val(&self, key: String) -> Value {
-        for frame in self.memory_frame.iter() {
-            match frame.get(&key) {
-                Some(value) => return *value,
-                _ => ()
+    //Some(None) for a variable that is declared but not assigned yet
+    pub fn retrieve(&self, key: &str) -> Option<Option<&Value>> {
+        for frame in self.call_frames.last().unwrap().iter().rev() {
+            if let Some(value) = frame.get(key) {
+                return Some(value.as_ref());
             }
         }
-        unreachable!("Unable to find key: {} in memory", key)
+        self.call_frames[0][0].get(key).map(Option::as_ref)
     }
-}
\ No newline at end of file
+
+    //Lets an assigned variable be changed in place, without copying it out and back
+    pub fn retrieve_mut(&mut self, key: &str) -> Option<&mut Value> {
+        let call_frame = self.call_frames.len() - 1;
+        let slot = match self.call_frames[call_frame].iter().rposition(|frame| frame.contains_key(key)) {
+            Some(frame) => self.call_frames[call_frame][frame].get_mut(key),
+            None => self.call_frames[0][0].get_mut(key)
+        };
+        slot.and_then(Option::as_mut)
+    }
+
+    fn current_call_frame(&mut self) -> &mut Vec<HashMap<String, Option<Value>>> {
+        self.call_frames.last_mut().unwrap()
+    }
+}
@@ -1,3 +1,5 @@
+pub mod builtins;
+pub mod error;
 pub mod interpreter;
 pub mod value;
-pub mod memory;
\ No newline at end of file
+pub mod memory;
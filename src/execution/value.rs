@@ -1,169 +1,513 @@
+use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::convert::TryInto;
-use std::fmt::{Display, Formatter, Result};
-use std::ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Sub};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use std::ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Neg, Rem, Shl, Shr, Sub};
 
-#[derive(Clone, PartialEq, Copy)]
+use crate::diagnostic::code;
+use crate::execution::error::RuntimeError;
+use crate::parsing::ast::Type;
+use crate::parsing::token::{Operator, UnaryOperator};
+
+#[derive(Clone, PartialEq, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum Value {
     IntegerValue(i32),
     FloatValue(f32),
     BooleanValue(bool),
+    StringValue(String),
+    //Struct name and fields in declaration order, copied on assignment like every other value
+    StructValue(String, Vec<(String, Value)>),
+    //Enum name, variant and the values of its payload
+    EnumValue(String, String, Vec<Value>),
+    ArrayValue(Vec<Value>),
+    MapValue(BTreeMap<Key, Value>),
     Unit,
 }
 
+//Structs and enums declared by the programs, the values given by the host are checked against them
+pub trait TypeDeclarations {
+    //Fields of a struct in declaration order
+    fn struct_fields(&self, id: &str) -> Option<&[(Type, String)]>;
+    //Variants of an enum with the types of their payload
+    fn enum_variants(&self, id: &str) -> Option<&[(String, Vec<Type>)]>;
+}
+
+//Values that can be the key of a map, ordered so that the entries of a map are visited in the order of their keys
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Key {
+    Integer(i32),
+    Boolean(bool),
+    String(String),
+}
+
+impl TryFrom<Value> for Key {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::IntegerValue(val) => Ok(Key::Integer(val)),
+            Value::BooleanValue(val) => Ok(Key::Boolean(val)),
+            Value::StringValue(val) => Ok(Key::String(val)),
+            value => Err(invalid_operand("hashing", value))
+        }
+    }
+}
+
+impl From<Key> for Value {
+    fn from(key: Key) -> Self {
+        match key {
+            Key::Integer(val) => Value::IntegerValue(val),
+            Key::Boolean(val) => Value::BooleanValue(val),
+            Key::String(val) => Value::StringValue(val)
+        }
+    }
+}
+
 impl Value {
-    pub fn expect_bool(self) -> bool {
+    pub fn value_type(&self) -> Type {
+        match self {
+            Value::IntegerValue(_) => Type::Integer,
+            Value::FloatValue(_) => Type::FloatingPoint,
+            Value::BooleanValue(_) => Type::Boolean,
+            Value::StringValue(_) => Type::String,
+            Value::StructValue(id, _) | Value::EnumValue(id, _, _) => Type::Custom(id.clone()),
+            //The type of the elements is unknown for an empty array, it fits any array
+            Value::ArrayValue(elements) =>
+                Type::Array(Box::new(elements.first().map_or(Type::Error, Value::value_type))),
+            Value::MapValue(entries) => match entries.iter().next() {
+                Some((key, value)) => Type::Map(Box::new(Value::from(key.clone()).value_type()), Box::new(value.value_type())),
+                None => Type::Map(Box::new(Type::Error), Box::new(Type::Error))
+            },
+            Value::Unit => Type::Unit
+        }
+    }
+
+    //Whether the value can be stored where `d_type` is declared, every element of a collection, field of a struct
+    //and value of a payload is checked against its declaration
+    pub fn has_type(&self, d_type: &Type, declarations: &impl TypeDeclarations) -> bool {
+        match (self, d_type) {
+            (Value::ArrayValue(elements), Type::Array(element)) =>
+                elements.iter().all(|value| value.has_type(element, declarations)),
+            (Value::MapValue(entries), Type::Map(key, value)) => entries.iter()
+                .all(|(k, v)| Value::from(k.clone()).has_type(key, declarations) && v.has_type(value, declarations)),
+            (Value::StructValue(id, fields), Type::Custom(d_id)) if id == d_id => match declarations.struct_fields(id) {
+                Some(declared) => fields.len() == declared.len() && fields.iter().zip(declared)
+                    .all(|((field, value), (f_type, name))| field == name && value.has_type(f_type, declarations)),
+                None => false
+            },
+            (Value::EnumValue(id, variant, payload), Type::Custom(d_id)) if id == d_id => {
+                let declared = declarations.enum_variants(id)
+                    .and_then(|variants| variants.iter().find(|(name, _)| name == variant));
+                match declared {
+                    Some((_, types)) => payload.len() == types.len() && payload.iter().zip(types)
+                        .all(|(value, p_type)| value.has_type(p_type, declarations)),
+                    None => false
+                }
+            }
+            (value, d_type) => value.value_type() == *d_type
+        }
+    }
+
+    pub fn printable(self) -> String {
+        match self {
+            Value::BooleanValue(val) => val.to_string(),
+            Value::FloatValue(val) => format!("{}", val),
+            Value::IntegerValue(val) => format!("{}", val),
+            Value::StringValue(val) => val,
+            Value::StructValue(id, fields) => {
+                let fields: Vec<String> = fields.into_iter()
+                    .map(|(field, value)| format!("{}: {}", field, value.printable()))
+                    .collect();
+                format!("{} {{ {} }}", id, fields.join(", "))
+            }
+            Value::EnumValue(id, variant, payload) if payload.is_empty() => format!("{}::{}", id, variant),
+            Value::EnumValue(id, variant, payload) => {
+                let payload: Vec<String> = payload.into_iter().map(Value::printable).collect();
+                format!("{}::{}({})", id, variant, payload.join(", "))
+            }
+            Value::ArrayValue(elements) => {
+                let elements: Vec<String> = elements.into_iter().map(Value::printable).collect();
+                format!("[{}]", elements.join(", "))
+            }
+            Value::MapValue(entries) if entries.is_empty() => "[:]".to_string(),
+            Value::MapValue(entries) => {
+                let entries: Vec<String> = entries.into_iter()
+                    .map(|(key, value)| format!("{}: {}", Value::from(key).printable(), value.printable()))
+                    .collect();
+                format!("[{}]", entries.join(", "))
+            }
+            Value::Unit => "unit".to_string()
+        }
+    }
+
+    pub fn expect_bool(&self) -> Result<bool, RuntimeError> {
+        match self {
+            Value::BooleanValue(val) => Ok(*val),
+            value => Err(unexpected_type(Type::Boolean, value))
+        }
+    }
+    pub fn expect_int(&self) -> Result<i32, RuntimeError> {
         match self {
-            Value::BooleanValue(val) => val,
-            _ => unreachable!("Runtime error: expected boolean value")
+            Value::IntegerValue(val) => Ok(*val),
+            value => Err(unexpected_type(Type::Integer, value))
+        }
+    }
+    pub fn expect_float(&self) -> Result<f32, RuntimeError> {
+        match self {
+            Value::FloatValue(val) => Ok(*val),
+            value => Err(unexpected_type(Type::FloatingPoint, value))
+        }
+    }
+    pub fn expect_string(&self) -> Result<&str, RuntimeError> {
+        match self {
+            Value::StringValue(val) => Ok(val),
+            value => Err(unexpected_type(Type::String, value))
+        }
+    }
+
+    //Shared by the interpreter and by the constant folding of the analyzer, so both agree on the result
+    pub fn binary_operation(self, op: Operator, rhs: Value) -> Result<Value, RuntimeError> {
+        match op {
+            Operator::Add => self + rhs,
+            Operator::Sub => self - rhs,
+            Operator::Mul => self * rhs,
+            Operator::Div => self / rhs,
+            Operator::Rem => self % rhs,
+            Operator::Pow => self.pow(rhs),
+            Operator::BitAnd => self & rhs,
+            Operator::BitOr => self | rhs,
+            Operator::BitXor => self ^ rhs,
+            Operator::Shl => self << rhs,
+            Operator::Shr => self >> rhs,
+            Operator::And | Operator::Or => match (self, rhs) {
+                (Value::BooleanValue(this), Value::BooleanValue(rhs)) =>
+                    Ok(Value::BooleanValue(if op == Operator::And { this && rhs } else { this || rhs })),
+                (left, right) => Err(invalid_operands("logical operation", left, right))
+            },
+            Operator::Eq => Ok(Value::BooleanValue(self == rhs)),
+            Operator::Ne => Ok(Value::BooleanValue(self != rhs)),
+            Operator::Gt => self.compare(&rhs).map(|order| Value::BooleanValue(order == Some(Ordering::Greater))),
+            Operator::Ge => self.compare(&rhs)
+                .map(|order| Value::BooleanValue(matches!(order, Some(Ordering::Greater | Ordering::Equal)))),
+            Operator::Lt => self.compare(&rhs).map(|order| Value::BooleanValue(order == Some(Ordering::Less))),
+            Operator::Le => self.compare(&rhs)
+                .map(|order| Value::BooleanValue(matches!(order, Some(Ordering::Less | Ordering::Equal))))
+        }
+    }
+
+    //`!` is only the logical not and `~` only the bitwise one, unlike Rust where both are `!`
+    pub fn unary_operation(self, op: UnaryOperator) -> Result<Value, RuntimeError> {
+        match (op, self) {
+            (UnaryOperator::Neg, value) => -value,
+            (UnaryOperator::Not, Value::BooleanValue(val)) => Ok(Value::BooleanValue(!val)),
+            (UnaryOperator::BitNot, Value::IntegerValue(val)) => Ok(Value::IntegerValue(!val)),
+            (UnaryOperator::Not, value) => Err(invalid_operand("logical not", value)),
+            (UnaryOperator::BitNot, value) => Err(invalid_operand("bitwise not", value))
+        }
+    }
+
+    pub fn pow(self, rhs: Value) -> Result<Value, RuntimeError> {
+        match (self, rhs) {
+            (Value::IntegerValue(this), Value::IntegerValue(rhs)) => match u32::try_from(rhs) {
+                Ok(exponent) => this.checked_pow(exponent).map(Value::IntegerValue).ok_or_else(|| overflow("power", this, rhs)),
+                Err(_) => Err(RuntimeError::new(code::NEGATIVE_EXPONENT,
+                                                format!("Cannot raise an integer to the negative power {}", rhs)))
+            },
+            (Value::FloatValue(this), Value::FloatValue(rhs)) => Ok(Value::FloatValue(this.powf(rhs))),
+            (left, right) => Err(invalid_operands("power", left, right))
         }
     }
-    pub fn expect_int(self) -> i32 {
+
+    pub fn field(&self, field: &str) -> Result<&Value, RuntimeError> {
         match self {
-            Value::IntegerValue(val) => val,
-            _ => unreachable!("Runtime error: expected int value")
+            Value::StructValue(_, fields) => fields.iter()
+                .find(|(name, _)| name == field)
+                .map(|(_, value)| value)
+                .ok_or_else(|| no_field(self, field)),
+            _ => Err(no_field(self, field))
         }
     }
-    pub fn expect_float(self) -> f32 {
+
+    pub fn field_mut(&mut self, field: &str) -> Result<&mut Value, RuntimeError> {
+        let error = no_field(self, field);
         match self {
-            Value::FloatValue(val) => val,
-            _ => unreachable!("Runtime error: expected float value")
+            Value::StructValue(_, fields) => fields.iter_mut()
+                .find(|(name, _)| name == field)
+                .map(|(_, value)| value)
+                .ok_or(error),
+            _ => Err(error)
+        }
+    }
+
+    //Strings are indexed by character, not by byte. The element of an array is borrowed, the character of a
+    //string is not stored in it and is built anew
+    pub fn index(&self, index: &Value) -> Result<Cow<'_, Value>, RuntimeError> {
+        match (self, index) {
+            (Value::StringValue(this), Value::IntegerValue(index)) => usize::try_from(*index).ok()
+                .and_then(|position| this.chars().nth(position))
+                .map(|c| Cow::Owned(Value::StringValue(c.to_string())))
+                .ok_or_else(|| RuntimeError::new(code::INDEX_OUT_OF_BOUNDS,
+                                                 format!("Index {} is out of bounds for a string of length {}",
+                                                         index, this.chars().count()))),
+            (Value::ArrayValue(elements), Value::IntegerValue(position)) => usize::try_from(*position).ok()
+                .and_then(|position| elements.get(position))
+                .map(Cow::Borrowed)
+                .ok_or_else(|| out_of_bounds(*position, elements.len())),
+            _ => Err(invalid_operands("indexing", self.clone(), index.clone()))
+        }
+    }
+
+    //Only the elements of arrays can be assigned, strings cannot be changed
+    pub fn index_mut(&mut self, index: &Value) -> Result<&mut Value, RuntimeError> {
+        match (self, index) {
+            (Value::ArrayValue(elements), Value::IntegerValue(position)) => {
+                let length = elements.len();
+                usize::try_from(*position).ok()
+                    .and_then(move |position| elements.get_mut(position))
+                    .ok_or_else(|| out_of_bounds(*position, length))
+            }
+            (this, index) => Err(invalid_operands("index assignment", this.clone(), index.clone()))
+        }
+    }
+
+    //Methods of the collections, the ones changing the collection are called on the value stored in the variable
+    pub fn call_method(&mut self, method: &str, mut arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        match (self, method) {
+            (Value::StringValue(this), "len") => i32::try_from(this.chars().count())
+                .map(Value::IntegerValue)
+                .map_err(|_| RuntimeError::new(code::INTEGER_OVERFLOW, "String too long to be measured".to_string())),
+            (Value::ArrayValue(elements), "len") => i32::try_from(elements.len())
+                .map(Value::IntegerValue)
+                .map_err(|_| RuntimeError::new(code::INTEGER_OVERFLOW, "Array too long to be measured".to_string())),
+            (Value::ArrayValue(elements), "push") if arguments.len() == 1 => {
+                elements.push(arguments.remove(0));
+                Ok(Value::Unit)
+            }
+            (Value::ArrayValue(elements), "pop") => elements.pop().ok_or_else(|| {
+                RuntimeError::new(code::INDEX_OUT_OF_BOUNDS, "Cannot pop an element from an empty array".to_string())
+            }),
+            (Value::MapValue(entries), "len") => i32::try_from(entries.len())
+                .map(Value::IntegerValue)
+                .map_err(|_| RuntimeError::new(code::INTEGER_OVERFLOW, "Map too large to be measured".to_string())),
+            (Value::MapValue(entries), "insert") if arguments.len() == 2 => {
+                let value = arguments.remove(1);
+                entries.insert(Key::try_from(arguments.remove(0))?, value);
+                Ok(Value::Unit)
+            }
+            (Value::MapValue(entries), "get") if arguments.len() == 1 => {
+                let key = Key::try_from(arguments.remove(0))?;
+                entries.get(&key).cloned().ok_or_else(|| missing_key(key))
+            }
+            (Value::MapValue(entries), "remove") if arguments.len() == 1 =>
+                Ok(Value::BooleanValue(entries.remove(&Key::try_from(arguments.remove(0))?).is_some())),
+            (Value::MapValue(entries), "contains") if arguments.len() == 1 =>
+                Ok(Value::BooleanValue(entries.contains_key(&Key::try_from(arguments.remove(0))?))),
+            (Value::MapValue(entries), "keys") => Ok(Value::ArrayValue(entries.keys().cloned().map(Value::from).collect())),
+            (Value::MapValue(entries), "values") => Ok(Value::ArrayValue(entries.values().cloned().collect())),
+            (this, method) => Err(RuntimeError::new(code::INVALID_OPERATION,
+                                                    format!("A value of type {} has no method {}", this, method)))
+        }
+    }
+
+    //None when either side is a NaN float
+    pub fn compare(&self, other: &Value) -> Result<Option<Ordering>, RuntimeError> {
+        match (self, other) {
+            (Value::IntegerValue(this), Value::IntegerValue(other)) => Ok(this.partial_cmp(other)),
+            (Value::FloatValue(this), Value::FloatValue(other)) => Ok(this.partial_cmp(other)),
+            _ => Err(invalid_operands("comparison", self.clone(), other.clone()))
         }
     }
 }
 
 impl Display for Value {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Value::IntegerValue(_) => write!(f, "integer"),
             Value::FloatValue(_) => write!(f, "float"),
             Value::BooleanValue(_) => write!(f, "boolean"),
+            Value::StringValue(_) => write!(f, "string"),
+            Value::StructValue(id, _) | Value::EnumValue(id, _, _) => write!(f, "{}", id),
+            Value::ArrayValue(_) => write!(f, "array"),
+            Value::MapValue(_) => write!(f, "map"),
             Value::Unit => write!(f, "unit")
         }
     }
 }
 
 impl Add for Value {
-    type Output = Self;
+    type Output = Result<Self, RuntimeError>;
     fn add(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Value::IntegerValue(this), Value::IntegerValue(rhs)) =>
+                this.checked_add(rhs).map(Value::IntegerValue).ok_or_else(|| overflow("addition", this, rhs)),
+            (Value::FloatValue(this), Value::FloatValue(rhs)) => Ok(Value::FloatValue(this + rhs)),
+            (Value::StringValue(this), Value::StringValue(rhs)) => Ok(Value::StringValue(this + &rhs)),
+            (left, right) => Err(invalid_operands("addition", left, right))
+        }
+    }
+}
+
+impl Neg for Value {
+    type Output = Result<Self, RuntimeError>;
+    fn neg(self) -> Self::Output {
         match self {
-            Value::IntegerValue(this) => Value::IntegerValue(this + rhs.expect_int()),
-            Value::FloatValue(this) => Value::FloatValue(this + rhs.expect_float()),
-            _ => unreachable!("Error while executing: {}, {} in an addition block", self, rhs)
+            Value::IntegerValue(this) => this.checked_neg().map(Value::IntegerValue).ok_or_else(|| {
+                RuntimeError::new(code::INTEGER_OVERFLOW, format!("Integer overflow in the negation of {}", this))
+            }),
+            Value::FloatValue(this) => Ok(Value::FloatValue(-this)),
+            value => Err(invalid_operand("negation", value))
         }
     }
 }
 
 impl Sub for Value {
-    type Output = Self;
+    type Output = Result<Self, RuntimeError>;
     fn sub(self, rhs: Self) -> Self::Output {
-        match self {
-            Value::IntegerValue(this) => Value::IntegerValue(this - rhs.expect_int()),
-            Value::FloatValue(this) => Value::FloatValue(this - rhs.expect_float()),
-            _ => unreachable!("Error while executing: {}, {} in a subtraction block", self, rhs)
+        match (self, rhs) {
+            (Value::IntegerValue(this), Value::IntegerValue(rhs)) =>
+                this.checked_sub(rhs).map(Value::IntegerValue).ok_or_else(|| overflow("subtraction", this, rhs)),
+            (Value::FloatValue(this), Value::FloatValue(rhs)) => Ok(Value::FloatValue(this - rhs)),
+            (left, right) => Err(invalid_operands("subtraction", left, right))
         }
     }
 }
 
 impl Mul for Value {
-    type Output = Self;
+    type Output = Result<Self, RuntimeError>;
     fn mul(self, rhs: Self) -> Self::Output {
-        match self {
-            Value::IntegerValue(this) => Value::IntegerValue(this * rhs.expect_int()),
-            Value::FloatValue(this) => Value::FloatValue(this * rhs.expect_float()),
-            _ => unreachable!("Error while executing: {}, {} in a multiplication block", self, rhs)
+        match (self, rhs) {
+            (Value::IntegerValue(this), Value::IntegerValue(rhs)) =>
+                this.checked_mul(rhs).map(Value::IntegerValue).ok_or_else(|| overflow("multiplication", this, rhs)),
+            (Value::FloatValue(this), Value::FloatValue(rhs)) => Ok(Value::FloatValue(this * rhs)),
+            (left, right) => Err(invalid_operands("multiplication", left, right))
         }
     }
 }
 
 impl Div for Value {
-    type Output = Self;
+    type Output = Result<Self, RuntimeError>;
     fn div(self, rhs: Self) -> Self::Output {
-        match self {
-            Value::IntegerValue(this) => Value::IntegerValue(this / rhs.expect_int()),
-            Value::FloatValue(this) => Value::FloatValue(this / rhs.expect_float()),
-            _ => unreachable!("Error while executing: {}, {} in a division block", self, rhs)
+        match (self, rhs) {
+            (Value::IntegerValue(_), Value::IntegerValue(0)) =>
+                Err(RuntimeError::new(code::DIVISION_BY_ZERO, "Integer division by zero".to_string())),
+            (Value::IntegerValue(this), Value::IntegerValue(rhs)) =>
+                this.checked_div(rhs).map(Value::IntegerValue).ok_or_else(|| overflow("division", this, rhs)),
+            (Value::FloatValue(this), Value::FloatValue(rhs)) => Ok(Value::FloatValue(this / rhs)),
+            (left, right) => Err(invalid_operands("division", left, right))
         }
     }
 }
 
-impl BitXor for Value {
-    type Output = Self;
-    fn bitxor(self, rhs: Self) -> Self::Output {
-        match self {
-            Value::IntegerValue(this) => Value::IntegerValue(this.pow(rhs.expect_int().try_into().unwrap())),
-            Value::FloatValue(this) => Value::FloatValue(this.powf(rhs.expect_float())),
-            Value::BooleanValue(this) => Value::BooleanValue(this ^ rhs.expect_bool()),
-            _ => unreachable!("Error while executing: {}, {} in a pow/xor block", self, rhs)
+impl Rem for Value {
+    type Output = Result<Self, RuntimeError>;
+    fn rem(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Value::IntegerValue(_), Value::IntegerValue(0)) =>
+                Err(RuntimeError::new(code::DIVISION_BY_ZERO, "Integer remainder of a division by zero".to_string())),
+            (Value::IntegerValue(this), Value::IntegerValue(rhs)) =>
+                this.checked_rem(rhs).map(Value::IntegerValue).ok_or_else(|| overflow("remainder", this, rhs)),
+            (Value::FloatValue(this), Value::FloatValue(rhs)) => Ok(Value::FloatValue(this % rhs)),
+            (left, right) => Err(invalid_operands("remainder", left, right))
         }
     }
 }
 
-impl BitAnd for Value {
-    type Output = Self;
-    fn bitand(self, rhs: Self) -> Self::Output {
-        match self {
-            Value::IntegerValue(this) => Value::IntegerValue(this & rhs.expect_int()),
-            Value::BooleanValue(this) => Value::BooleanValue(this && rhs.expect_bool()),
-            _ => unreachable!("Error while executing: {}, {} in a and block", self, rhs)
+impl BitXor for Value {
+    type Output = Result<Self, RuntimeError>;
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Value::IntegerValue(this), Value::IntegerValue(rhs)) => Ok(Value::IntegerValue(this ^ rhs)),
+            (left, right) => Err(invalid_operands("bitwise xor", left, right))
         }
     }
 }
 
-impl BitOr for Value {
-    type Output = Self;
-    fn bitor(self, rhs: Self) -> Self::Output {
-        match self {
-            Value::IntegerValue(this) => Value::IntegerValue(this | rhs.expect_int()),
-            Value::BooleanValue(this) => Value::BooleanValue(this || rhs.expect_bool()),
-            _ => unreachable!("Error while executing: {}, {} in a or block", self, rhs)
+//Shifting by 32 bits or more, or by a negative amount, is an error instead of wrapping the amount like Rust
+impl Shl for Value {
+    type Output = Result<Self, RuntimeError>;
+    fn shl(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Value::IntegerValue(this), Value::IntegerValue(rhs)) =>
+                shift_amount(rhs).map(|amount| Value::IntegerValue(this << amount)),
+            (left, right) => Err(invalid_operands("left shift", left, right))
         }
     }
 }
 
-impl PartialOrd for Value {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match self {
-            Value::IntegerValue(this) => this.partial_cmp(&other.expect_int()),
-            Value::FloatValue(this) => this.partial_cmp(&other.expect_float()),
-            Value::BooleanValue(this) => this.partial_cmp(&other.expect_bool()),
-            _ => unreachable!("Error while executing: {}, {} in a eq block", self, other)
+//Arithmetic shift, the sign is kept
+impl Shr for Value {
+    type Output = Result<Self, RuntimeError>;
+    fn shr(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Value::IntegerValue(this), Value::IntegerValue(rhs)) =>
+                shift_amount(rhs).map(|amount| Value::IntegerValue(this >> amount)),
+            (left, right) => Err(invalid_operands("right shift", left, right))
         }
     }
+}
 
-    fn lt(&self, other: &Self) -> bool {
-        match self {
-            Value::IntegerValue(this) => this.lt(&other.expect_int()),
-            Value::FloatValue(this) => this.lt(&&other.expect_float()),
-            Value::BooleanValue(this) => this.lt(&other.expect_bool()),
-            _ => unreachable!("Error while executing: {}, {} in a eq block", self, other)
+impl BitAnd for Value {
+    type Output = Result<Self, RuntimeError>;
+    fn bitand(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Value::IntegerValue(this), Value::IntegerValue(rhs)) => Ok(Value::IntegerValue(this & rhs)),
+            (left, right) => Err(invalid_operands("bitwise and", left, right))
         }
     }
+}
 
-    fn le(&self, other: &Self) -> bool {
-        match self {
-            Value::IntegerValue(this) => this.le(&other.expect_int()),
-            Value::FloatValue(this) => this.le(&&other.expect_float()),
-            Value::BooleanValue(this) => this.le(&other.expect_bool()),
-            _ => unreachable!("Error while executing: {}, {} in a eq block", self, other)
+impl BitOr for Value {
+    type Output = Result<Self, RuntimeError>;
+    fn bitor(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Value::IntegerValue(this), Value::IntegerValue(rhs)) => Ok(Value::IntegerValue(this | rhs)),
+            (left, right) => Err(invalid_operands("bitwise or", left, right))
         }
     }
+}
 
-    fn gt(&self, other: &Self) -> bool {
-        match self {
-            Value::IntegerValue(this) => this.gt(&other.expect_int()),
-            Value::FloatValue(this) => this.gt(&&other.expect_float()),
-            Value::BooleanValue(this) => this.gt(&other.expect_bool()),
-            _ => unreachable!("Error while executing: {}, {} in a eq block", self, other)
-        }
-    }
+//Only reachable when the program was not analyzed, or reads a struct that was declared but never assigned
+fn no_field(value: &Value, field: &str) -> RuntimeError {
+    RuntimeError::new(code::INVALID_OPERATION, format!("A value of type {} has no field {}", value, field))
+}
 
-    fn ge(&self, other: &Self) -> bool {
-        match self {
-            Value::IntegerValue(this) => this.ge(&other.expect_int()),
-            Value::FloatValue(this) => this.ge(&&other.expect_float()),
-            Value::BooleanValue(this) => this.ge(&other.expect_bool()),
-            _ => unreachable!("Error while executing: {}, {} in a eq block", self, other)
-        }
+fn out_of_bounds(index: i32, length: usize) -> RuntimeError {
+    RuntimeError::new(code::INDEX_OUT_OF_BOUNDS,
+                      format!("Index {} is out of bounds for an array of length {}", index, length))
+}
+
+fn shift_amount(amount: i32) -> Result<u32, RuntimeError> {
+    match u32::try_from(amount) {
+        Ok(amount) if amount < 32 => Ok(amount),
+        _ => Err(RuntimeError::new(code::INVALID_SHIFT,
+                                   format!("Cannot shift an integer by {} bits, the amount must be between 0 and 31", amount)))
     }
 }
+
+fn overflow(operation: &str, left: i32, right: i32) -> RuntimeError {
+    RuntimeError::new(code::INTEGER_OVERFLOW,
+                      format!("Integer overflow in the {} of {} and {}", operation, left, right))
+}
+
+fn missing_key(key: Key) -> RuntimeError {
+    RuntimeError::new(code::MISSING_KEY, format!("Key {} is not in the map", Value::from(key).printable()))
+}
+
+//Raised by the expect_ helpers, a native function called from the host or from a value the analyzer never saw
+//can receive anything
+fn unexpected_type(expected: Type, value: &Value) -> RuntimeError {
+    RuntimeError::new(code::INVALID_OPERATION,
+                      format!("Expected a value of type {} but found a value of type {}", expected, value.value_type()))
+}
+
+fn invalid_operand(operation: &str, value: Value) -> RuntimeError {
+    RuntimeError::new(code::INVALID_OPERATION, format!("Cannot perform {} on a value of type {}", operation, value))
+}
+
+//Only reachable when the program was not analyzed, or reads a variable that was declared but never assigned
+fn invalid_operands(operation: &str, left: Value, right: Value) -> RuntimeError {
+    RuntimeError::new(code::INVALID_OPERATION,
+                      format!("Cannot perform {} on values of type {} and {}", operation, left, right))
+}
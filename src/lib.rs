@@ -0,0 +1,14 @@
+//Diagnostics are large but only built on the error path
+#![allow(clippy::result_large_err)]
+
+pub use crate::diagnostic::{Diagnostic, Phase, Renderer, Severity, Span};
+pub use crate::engine::{Engine, Program};
+pub use crate::execution::error::RuntimeError;
+pub use crate::execution::value::Value;
+pub use crate::parsing::ast::Type;
+
+pub mod diagnostic;
+pub mod parsing;
+pub mod execution;
+pub mod macros;
+mod engine;
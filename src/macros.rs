@@ -51,9 +51,6 @@ pub trait Stack<T> {
 
 impl<T: Clone> Stack<T> for Vec<T> {
     fn top(&self) -> Option<T> {
-        if self.is_empty() {
-            return None;
-        }
-        self.get(self.len() - 1).map(|value| value.clone())
+        self.last().cloned()
     }
 }
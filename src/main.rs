@@ -1,36 +1,193 @@
+//Diagnostics are large but only built on the error path
+#![allow(clippy::result_large_err)]
+
+use std::env;
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, IsTerminal, Read};
+use std::process;
+use std::thread;
+
+use thatlang::{Engine, Phase, Renderer};
+
+use crate::repl::Repl;
+
+mod repl;
+
+const USAGE: &str = "Usage:
+    thatlang run <file>      Lex, parse, analyze and execute a file
+    thatlang check <file>    Lex, parse and analyze a file without executing it
+    thatlang repl            Start an interactive session
+
+<file> can be '-' to read the program from stdin, or '-e <code>' to pass it inline.
 
-use crate::execution::interpreter::Interpreter;
-use crate::parsing::analyzer::StaticAnalyzer;
-use crate::parsing::lexer::Lexer;
-use crate::parsing::parser::Parser;
+Options:
+    --color=<when>           Color the error reports: auto (default), always or never";
 
-mod parsing;
-mod execution;
-pub mod macros;
+//Every failing phase has its own exit code so that scripts can tell them apart
+const EXIT_USAGE: i32 = 1;
+const EXIT_LEX: i32 = 2;
+const EXIT_PARSE: i32 = 3;
+const EXIT_ANALYZE: i32 = 4;
+const EXIT_RUNTIME: i32 = 5;
+
+//Calls in the scripts recurse on the native stack, the one of the main thread is too small
+//for deep recursions
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+//Part of the stack left to the driver and to the native functions
+const STACK_RESERVE: usize = 8 * 1024 * 1024;
+
+enum Command {
+    Run,
+    Check,
+}
+
+enum Source {
+    File(String),
+    Stdin,
+    Inline(String),
+}
+
+impl Source {
+    fn name(&self) -> String {
+        match self {
+            Source::File(path) => path.to_string(),
+            Source::Stdin => "<stdin>".to_string(),
+            Source::Inline(_) => "<inline>".to_string()
+        }
+    }
+}
 
 fn main() {
-    let mut file = File::open("example/fib.mps").unwrap();
+    let cli = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(cli)
+        .expect("Unable to start the interpreter thread");
+    //The panic message was already printed by the hook
+    process::exit(cli.join().unwrap_or(101))
+}
+
+fn cli() -> i32 {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let color = match take_color_option(&mut args) {
+        Ok(color) => color,
+        Err(error) => {
+            eprintln!("ERROR: {}\n\n{}", error, USAGE);
+            process::exit(EXIT_USAGE)
+        }
+    };
+    if args.len() == 1 && args[0] == "repl" {
+        Repl::new(color).run();
+        return 0;
+    }
+    let (command, source) = match parse_arguments(&args) {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            eprintln!("ERROR: {}\n\n{}", error, USAGE);
+            process::exit(EXIT_USAGE)
+        }
+    };
+    let name = source.name();
+    let contents = match read_source(source) {
+        Ok(contents) => contents,
+        Err(error) => {
+            eprintln!("ERROR while reading the program: {}", error);
+            process::exit(EXIT_USAGE)
+        }
+    };
+    let renderer = Renderer::new(&contents, &name).with_color(color);
+    execute(command, &contents, &renderer)
+}
+
+//Removes the --color option from the arguments, returning whether reports must be colored
+fn take_color_option(args: &mut Vec<String>) -> Result<bool, String> {
+    let mut color = io::stderr().is_terminal();
+    let mut result = Ok(());
+    args.retain(|arg| match arg.strip_prefix("--color=") {
+        Some("always") => {
+            color = true;
+            false
+        }
+        Some("never") => {
+            color = false;
+            false
+        }
+        Some("auto") => false,
+        Some(unexpected) => {
+            result = Err(format!("Unknown color option '{}'", unexpected));
+            false
+        }
+        None => true
+    });
+    result.map(|_| color)
+}
+
+fn parse_arguments(args: &[String]) -> Result<(Command, Source), String> {
+    let command = match args.first().map(String::as_str) {
+        Some("run") => Command::Run,
+        Some("check") => Command::Check,
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            process::exit(0)
+        }
+        Some(unexpected) => return Err(format!("Unknown command '{}'", unexpected)),
+        None => return Err("Missing command".to_string())
+    };
+    let source = match &args[1..] {
+        [flag, code] if flag == "-e" => Source::Inline(code.to_string()),
+        [dash] if dash == "-" => Source::Stdin,
+        [path] if !path.starts_with('-') => Source::File(path.to_string()),
+        [] => return Err("Missing program to execute".to_string()),
+        _ => return Err(format!("Unexpected arguments '{}'", args[1..].join(" ")))
+    };
+    Ok((command, source))
+}
+
+fn read_source(source: Source) -> io::Result<String> {
     let mut contents = String::new();
-    file.read_to_string(&mut contents);
-    let mut lexer = Lexer::new(&contents);
-    match lexer.lex() {
-        Err(error) => println!("ERROR while lexing: {}", error),
-        Ok(tokens) => {
-            let mut parser = Parser::new(&tokens);
-            match parser.parse() {
-                Err(error) => println!("ERROR while parsing: {}", error),
-                Ok(ast) => {
-                    let mut analyzer = StaticAnalyzer::new();
-                    match analyzer.analyze(ast.clone()) {
-                        Some(error) => println!("ERROR while analyzing: {}", error),
-                        None => {
-                            let mut interpreter = Interpreter::new();
-                            interpreter.eval(ast)
-                        }
-                    }
-                }
+    match source {
+        Source::File(path) => {
+            File::open(path)?.read_to_string(&mut contents)?;
+        }
+        Source::Stdin => {
+            io::stdin().read_to_string(&mut contents)?;
+        }
+        Source::Inline(code) => contents = code
+    }
+    Ok(contents)
+}
+
+//Engine for the interpreter thread, which has room for deeper recursions than the default
+fn new_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_stack_limit(STACK_SIZE - STACK_RESERVE);
+    engine
+}
+
+fn execute(command: Command, contents: &str, renderer: &Renderer) -> i32 {
+    let mut engine = new_engine();
+    let compiled = engine.compile(contents);
+    for warning in engine.warnings() {
+        eprintln!("{}", renderer.render(warning));
+    }
+    let result = compiled.and_then(|program| match command {
+        Command::Run => engine.run(program).map_err(|error| vec![error]),
+        Command::Check => Ok(())
+    });
+    match result {
+        Ok(()) => 0,
+        Err(errors) => {
+            for error in errors.iter() {
+                eprintln!("{}", renderer.render(error));
+            }
+            if errors.len() > 1 {
+                eprintln!("{} errors reported", errors.len());
+            }
+            //Errors are only ever reported for a single phase
+            match errors[0].phase {
+                Phase::Lexing => EXIT_LEX,
+                Phase::Parsing => EXIT_PARSE,
+                Phase::Analysis => EXIT_ANALYZE,
+                Phase::Runtime => EXIT_RUNTIME
             }
         }
     }
@@ -1,135 +1,1186 @@
-use crate::option_propagate_failure_to_option;
-use crate::parsing::ast::{AST, Expression, Type};
+use std::collections::HashSet;
+
+use crate::diagnostic::{code, Diagnostic, Phase, Span};
+use crate::execution::error::RuntimeError;
+use crate::execution::value::Value;
+use crate::parsing::ast::{Accessor, AST, ASTKind, Expression, ExpressionKind, FunctionSignature, Iterable, MatchArm, Pattern,
+                          Type};
 use crate::parsing::symbol_table::SymbolTable;
-use crate::parsing::token::Operator;
-use crate::result_propagate_failure_to_option;
-use crate::result_propagate_failure_to_result;
+use crate::parsing::token::{Operator, UnaryOperator};
 
+#[derive(Clone)]
 pub struct StaticAnalyzer {
-    symbol_table: SymbolTable
+    symbol_table: SymbolTable,
+    //Return type of the function being analyzed, None at the top level
+    return_type: Option<Type>,
+    //Labels of the loops enclosing the analyzed statement, innermost last
+    loops: Vec<Option<String>>,
+    //Functions of the analyzed program declared before its first statement, until their declaration is reached
+    hoisted: HashSet<String>,
+}
+
+impl Default for StaticAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl StaticAnalyzer {
     pub fn new() -> Self {
         Self {
-            symbol_table: SymbolTable::new()
+            symbol_table: SymbolTable::new(),
+            return_type: None,
+            loops: Vec::new(),
+            hoisted: HashSet::new(),
+        }
+    }
+
+    pub fn global_variables(&self) -> Vec<(String, Type)> {
+        self.symbol_table.global_symbols()
+    }
+
+    pub fn global_type(&self, id: String) -> Option<Type> {
+        self.symbol_table.retrieve_global_type(id)
+    }
+
+    pub fn declare_function(&mut self, id: String, signature: FunctionSignature) -> bool {
+        self.symbol_table.declare_function(id, signature, None)
+    }
+
+    pub fn declare_global(&mut self, id: String, d_type: Type) -> bool {
+        self.symbol_table.declare_global(id, d_type)
+    }
+
+    //Whether every struct and enum named by the type was declared
+    pub fn is_type_declared(&self, d_type: &Type) -> bool {
+        match d_type {
+            Type::Array(element) => self.is_type_declared(element),
+            Type::Map(key, value) => self.is_type_declared(key) && self.is_type_declared(value),
+            Type::Custom(id) => self.symbol_table.is_type_declared(id),
+            _ => true
+        }
+    }
+
+    //Whether a value given by the host fits the declarations of the programs analyzed so far
+    pub fn has_type(&self, value: &Value, d_type: &Type) -> bool {
+        value.has_type(d_type, &self.symbol_table)
+    }
+
+    //Checks the whole tree, every error is reported instead of stopping at the first one
+    pub fn analyze(&mut self, ast: AST) -> Result<(), Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+        self.hoist_functions(&ast);
+        self.check(ast, &mut diagnostics);
+        if diagnostics.is_empty() { Ok(()) } else { Err(diagnostics) }
+    }
+
+    pub fn analyze_expression(&self, expr: Expression) -> Result<Type, Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+        let expr_type = self.check_expression(expr, &mut diagnostics);
+        if diagnostics.is_empty() { Ok(expr_type) } else { Err(diagnostics) }
+    }
+
+    //Functions can be called before their declaration, and call each other. Their types are reported when
+    //their declaration is analyzed, until then an unknown type is an Error.
+    fn hoist_functions(&mut self, ast: &AST) {
+        self.hoisted.clear();
+        let nodes = match &ast.kind {
+            ASTKind::Block(nodes) => nodes,
+            _ => return
+        };
+        for node in nodes {
+            if let ASTKind::FunctionDeclaration(id, parameters, return_type, _) = &node.kind {
+                let mut ignored = Vec::new();
+                let signature = FunctionSignature {
+                    parameters: parameters.iter()
+                        .map(|(p_type, _)| self.resolve_type(p_type.clone(), node.span, &mut ignored))
+                        .collect(),
+                    return_type: self.resolve_type(return_type.clone(), node.span, &mut ignored),
+                };
+                //A function declared twice is reported when its second declaration is analyzed
+                if self.symbol_table.declare_function(id.clone(), signature, Some(node.span)) {
+                    self.hoisted.insert(id.clone());
+                }
+            }
         }
     }
 
-    pub fn analyze(&mut self, ast: AST) -> Option<String> {
-        match ast {
-            AST::VarDeclaration(d_type, id) => {
-                if !self.symbol_table.declare(id.clone(), d_type) {
-                    return Some(format!("Cannot declare variable {} because it was already declared in this scope",
-                                        id.clone()));
+    fn check(&mut self, ast: AST, diagnostics: &mut Vec<Diagnostic>) {
+        let span = ast.span;
+        match ast.kind {
+            ASTKind::VarDeclaration(d_type, id) => {
+                let d_type = self.resolve_type(d_type, span, diagnostics);
+                if !self.symbol_table.declare(id.clone(), d_type, span) {
+                    diagnostics.push(self.already_declared(id, span));
                 }
-                None
             }
-            AST::VarDeclarationAndAssignment(d_type, id, expr) => {
-                if !self.symbol_table.declare(id.clone(), d_type.clone()) {
-                    return Some(format!("Cannot declare variable {} because it was already declared in this scope",
-                                        id.clone()));
+            ASTKind::VarDeclarationAndAssignment(d_type, id, expr) => {
+                let d_type = self.resolve_type(d_type, span, diagnostics);
+                let expr_span = expr.span;
+                let expr_type = self.check_expression(expr, diagnostics);
+                //Declared even if the expression is invalid, so that its uses are not reported
+                if !self.symbol_table.declare(id.clone(), d_type.clone(), span) {
+                    diagnostics.push(self.already_declared(id.clone(), span));
                 }
-                let expr_type = result_propagate_failure_to_option!(self.analyze_expression(expr));
-                if d_type.clone() != expr_type {
-                    return Some(format!("Mismatched types variable {} was declared {} but assigned {}",
-                                        id, d_type.clone(), expr_type));
+                if !fits(&d_type, &expr_type) {
+                    diagnostics.push(error(code::MISMATCHED_TYPES,
+                                           format!("Mismatched types variable {} was declared {} but assigned {}",
+                                                   id, d_type, expr_type),
+                                           expr_span)
+                        .with_label(format!("expected {}, found {}", d_type, expr_type)));
                 }
-                None
             }
-            AST::Assign(id, expr) => {
-                let id_type = result_propagate_failure_to_option!(self.symbol_table.retrieve_type(id.clone()),
-                                                                format!("Cannot assign to undeclared variable {}", id.clone()));
-                let expr_type = result_propagate_failure_to_option!(self.analyze_expression(expr));
-                if id_type != expr_type {
-                    return Some(format!("Mismatched types variable {} vas declared {} but assigned {}",
-                                        id.clone(), id_type, expr_type));
+            ASTKind::Assign(id, expr) => {
+                let id_type = self.assigned_type(id.clone(), span, diagnostics);
+                let expr_span = expr.span;
+                let expr_type = self.check_expression(expr, diagnostics);
+                if !fits(&id_type, &expr_type) {
+                    let mut diagnostic = error(code::MISMATCHED_TYPES,
+                                               format!("Mismatched types variable {} was declared {} but assigned {}",
+                                                       id, id_type, expr_type),
+                                               expr_span)
+                        .with_label(format!("expected {}, found {}", id_type, expr_type));
+                    if let Some(declaration) = self.symbol_table.retrieve_declaration(id.clone()) {
+                        diagnostic = diagnostic.with_secondary(declaration,
+                                                               format!("variable `{}` declared here as {}", id, id_type));
+                    }
+                    diagnostics.push(diagnostic);
                 }
-                None
             }
-            AST::Print(expr) => match self.analyze_expression(expr) {
-                Err(err) => Some(err),
-                Ok(_) => None
-            },
-            AST::Block(nodes) => {
-                for node in nodes {
-                    match self.analyze(node) {
-                        Some(err) => return Some(err),
-                        _ => ()
+            ASTKind::CompoundAssign(id, op, expr) => {
+                let id_type = self.assigned_type(id, span, diagnostics);
+                let expr_type = self.check_expression(expr, diagnostics);
+                if id_type == Type::Error || expr_type == Type::Error {
+                    return;
+                }
+                //The allowed operators give a value of the type of their left operand
+                let label = format!("{} {}= {}", id_type, op, expr_type);
+                if let Err(msg) = self.analyze_operator(id_type, op, expr_type) {
+                    diagnostics.push(error(code::INVALID_OPERANDS, msg, span).with_label(label));
+                }
+            }
+            ASTKind::MemberAssign(id, path, op, expr) => {
+                let mut member_type = self.assigned_type(id, span, diagnostics);
+                for accessor in path {
+                    member_type = match accessor {
+                        Accessor::Field(field) => self.field_type(member_type, field, span, diagnostics),
+                        Accessor::Index(index) => {
+                            let index_span = index.span;
+                            let index_type = self.check_expression(index, diagnostics);
+                            if member_type == Type::String {
+                                diagnostics.push(error(code::NOT_ASSIGNABLE,
+                                                       "Cannot assign to a character of a string".to_string(),
+                                                       span)
+                                    .with_label("strings cannot be changed".to_string())
+                                    .with_help("build a new string with substring and +".to_string()));
+                                Type::Error
+                            } else {
+                                element_type(member_type, index_type, span, index_span, diagnostics)
+                            }
+                        }
+                    };
+                }
+                let expr_span = expr.span;
+                let expr_type = self.check_expression(expr, diagnostics);
+                if member_type == Type::Error || expr_type == Type::Error {
+                    return;
+                }
+                match op {
+                    Some(op) => {
+                        let label = format!("{} {}= {}", member_type, op, expr_type);
+                        if let Err(msg) = self.analyze_operator(member_type, op, expr_type) {
+                            diagnostics.push(error(code::INVALID_OPERANDS, msg, span).with_label(label));
+                        }
+                    }
+                    None if !fits(&member_type, &expr_type) => {
+                        diagnostics.push(error(code::MISMATCHED_TYPES,
+                                               format!("Mismatched types member was declared {} but assigned {}",
+                                                       member_type, expr_type),
+                                               expr_span)
+                            .with_label(format!("expected {}, found {}", member_type, expr_type)));
                     }
+                    None => ()
                 }
-                None
             }
-            AST::IfStatement(cond, then) => {
-                result_propagate_failure_to_option!(self.analyze_expression(cond));
+            ASTKind::Print(expr) | ASTKind::ExpressionStatement(expr) => {
+                self.check_expression(expr, diagnostics);
+            }
+            ASTKind::Block(nodes) => {
+                for node in nodes {
+                    self.check(node, diagnostics);
+                }
+            }
+            ASTKind::IfStatement(cond, then, otherwise) => {
+                self.check_condition(cond, diagnostics);
                 self.symbol_table.create_frame();
-                option_propagate_failure_to_option!(self.analyze(*then));
+                self.check(*then, diagnostics);
                 self.symbol_table.remove_frame();
-                None
+                if let Some(otherwise) = otherwise {
+                    self.symbol_table.create_frame();
+                    self.check(*otherwise, diagnostics);
+                    self.symbol_table.remove_frame();
+                }
             }
-            AST::WhileStatement(cond, body) => {
-                result_propagate_failure_to_option!(self.analyze_expression(cond));
+            ASTKind::WhileStatement(label, cond, body) => {
+                self.check_condition(cond, diagnostics);
                 self.symbol_table.create_frame();
-                option_propagate_failure_to_option!(self.analyze(*body));
+                self.loops.push(label);
+                self.check(*body, diagnostics);
+                self.loops.pop();
                 self.symbol_table.remove_frame();
-                None
             }
-            AST::ForStatement(dec, cond, inc, body) => {
+            ASTKind::ForStatement(label, dec, cond, inc, body) => {
                 self.symbol_table.create_frame();
-                option_propagate_failure_to_option!(self.analyze(*dec));
-                result_propagate_failure_to_option!(self.analyze_expression(cond));
-                option_propagate_failure_to_option!(self.analyze(*inc));
-                option_propagate_failure_to_option!(self.analyze(*body));
+                self.check(*dec, diagnostics);
+                self.check_condition(cond, diagnostics);
+                self.check(*inc, diagnostics);
+                self.loops.push(label);
+                self.check(*body, diagnostics);
+                self.loops.pop();
                 self.symbol_table.remove_frame();
-                None
+            }
+            ASTKind::ForInStatement(label, bindings, iterable, body) => {
+                let types = self.iterated_types(iterable, bindings.len(), span, diagnostics);
+                self.symbol_table.create_frame();
+                for (binding, b_type) in bindings.into_iter().zip(types) {
+                    if let Some(binding) = binding {
+                        if !self.symbol_table.declare(binding.clone(), b_type, span) {
+                            diagnostics.push(self.already_declared(binding, span));
+                        }
+                    }
+                }
+                self.loops.push(label);
+                self.check(*body, diagnostics);
+                self.loops.pop();
+                self.symbol_table.remove_frame();
+            }
+            ASTKind::Break(label) => self.check_loop_control("break", label, span, diagnostics),
+            ASTKind::Continue(label) => self.check_loop_control("continue", label, span, diagnostics),
+            ASTKind::FunctionDeclaration(id, parameters, return_type, body) => {
+                if !self.symbol_table.is_global_scope() || self.return_type.is_some() {
+                    diagnostics.push(error(code::NESTED_FUNCTION,
+                                           format!("Function {} must be declared at the top level", id),
+                                           span)
+                        .with_help("move the declaration outside of any block or function".to_string()));
+                    return;
+                }
+                let parameters: Vec<(Type, String)> = parameters.into_iter()
+                    .map(|(p_type, parameter)| (self.resolve_type(p_type, span, diagnostics), parameter))
+                    .collect();
+                let return_type = self.resolve_type(return_type, span, diagnostics);
+                let signature = FunctionSignature {
+                    parameters: parameters.iter().map(|(p_type, _)| p_type.clone()).collect(),
+                    return_type: return_type.clone(),
+                };
+                if self.hoisted.remove(&id) {
+                    self.symbol_table.redeclare_function(id.clone(), signature);
+                } else if !self.symbol_table.declare_function(id.clone(), signature, Some(span)) {
+                    let mut diagnostic = error(code::ALREADY_DECLARED,
+                                               format!("Cannot declare function {} because it was already declared", id),
+                                               span);
+                    if let Some(declaration) = self.symbol_table.retrieve_function_declaration(id.clone()) {
+                        diagnostic = diagnostic.with_secondary(declaration, "previously declared here".to_string());
+                    }
+                    diagnostics.push(diagnostic);
+                }
+                if return_type != Type::Unit && return_type != Type::Error && !always_returns(&body) {
+                    diagnostics.push(error(code::MISSING_RETURN,
+                                           format!("Function {} does not return a value of type {} on every path",
+                                                   id, return_type),
+                                           span)
+                        .with_label(format!("expected to return {}", return_type))
+                        .with_help("end the body of the function with a return statement".to_string()));
+                }
+                self.symbol_table.create_call_frame();
+                self.return_type = Some(return_type);
+                for (p_type, parameter) in parameters {
+                    if !self.symbol_table.declare(parameter.clone(), p_type, span) {
+                        diagnostics.push(error(code::ALREADY_DECLARED,
+                                               format!("Parameter {} of function {} was declared twice", parameter, id),
+                                               span));
+                    }
+                }
+                self.check(*body, diagnostics);
+                self.return_type = None;
+                self.symbol_table.remove_call_frame();
+            }
+            ASTKind::StructDeclaration(id, fields) => {
+                if !self.symbol_table.is_global_scope() || self.return_type.is_some() {
+                    diagnostics.push(error(code::NESTED_STRUCT,
+                                           format!("Struct {} must be declared at the top level", id),
+                                           span)
+                        .with_help("move the declaration outside of any block or function".to_string()));
+                    return;
+                }
+                let mut declared: Vec<(Type, String)> = Vec::new();
+                for (f_type, field) in fields {
+                    //A struct cannot contain itself, it is only declared after its fields
+                    let f_type = self.resolve_type(f_type, span, diagnostics);
+                    if declared.iter().any(|(_, other)| *other == field) {
+                        diagnostics.push(error(code::ALREADY_DECLARED,
+                                               format!("Field {} of struct {} was declared twice", field, id),
+                                               span));
+                        continue;
+                    }
+                    declared.push((f_type, field));
+                }
+                if !self.symbol_table.declare_struct(id.clone(), declared, span) {
+                    let mut diagnostic = error(code::ALREADY_DECLARED,
+                                               format!("Cannot declare struct {} because it was already declared", id),
+                                               span);
+                    if let Some(declaration) = self.symbol_table.retrieve_type_declaration(&id) {
+                        diagnostic = diagnostic.with_secondary(declaration, "previously declared here".to_string());
+                    }
+                    diagnostics.push(diagnostic);
+                }
+            }
+            ASTKind::EnumDeclaration(id, variants) => {
+                if !self.symbol_table.is_global_scope() || self.return_type.is_some() {
+                    diagnostics.push(error(code::NESTED_ENUM,
+                                           format!("Enum {} must be declared at the top level", id),
+                                           span)
+                        .with_help("move the declaration outside of any block or function".to_string()));
+                    return;
+                }
+                let mut declared: Vec<(String, Vec<Type>)> = Vec::new();
+                for (variant, payload) in variants {
+                    //An enum cannot contain itself, it is only declared after its variants
+                    let payload = payload.into_iter()
+                        .map(|p_type| self.resolve_type(p_type, span, diagnostics))
+                        .collect();
+                    if declared.iter().any(|(other, _)| *other == variant) {
+                        diagnostics.push(error(code::ALREADY_DECLARED,
+                                               format!("Variant {} of enum {} was declared twice", variant, id),
+                                               span));
+                        continue;
+                    }
+                    declared.push((variant, payload));
+                }
+                if !self.symbol_table.declare_enum(id.clone(), declared, span) {
+                    let mut diagnostic = error(code::ALREADY_DECLARED,
+                                               format!("Cannot declare enum {} because it was already declared", id),
+                                               span);
+                    if let Some(declaration) = self.symbol_table.retrieve_type_declaration(&id) {
+                        diagnostic = diagnostic.with_secondary(declaration, "previously declared here".to_string());
+                    }
+                    diagnostics.push(diagnostic);
+                }
+            }
+            ASTKind::Match(value, arms) => {
+                let value_type = self.check_expression(value, diagnostics);
+                self.check_arms(&value_type, &arms, span, diagnostics);
+                for arm in arms {
+                    let bindings = self.check_pattern(&arm.pattern, &value_type, arm.span, diagnostics);
+                    self.symbol_table.create_frame();
+                    self.declare_bindings(bindings, arm.span, diagnostics);
+                    self.check(arm.body, diagnostics);
+                    self.symbol_table.remove_frame();
+                }
+            }
+            ASTKind::Return(expr) => {
+                let expr_type = match expr {
+                    Some(expr) => self.check_expression(expr, diagnostics),
+                    None => Type::Unit
+                };
+                let expected = match self.return_type.clone() {
+                    Some(expected) => expected,
+                    None => {
+                        diagnostics.push(error(code::RETURN_OUTSIDE_FUNCTION,
+                                               "Cannot return outside of a function".to_string(),
+                                               span));
+                        return;
+                    }
+                };
+                if !fits(&expected, &expr_type) {
+                    diagnostics.push(error(code::MISMATCHED_TYPES,
+                                           format!("Mismatched types function was declared returning {} but returns {}",
+                                                   expected, expr_type),
+                                           span)
+                        .with_label(format!("expected {}, found {}", expected, expr_type)));
+                }
             }
         }
     }
 
-    fn analyze_expression(&self, expr: Expression) -> Result<Type, String> {
-        match expr {
-            Expression::IntegerLiteral(_) => Ok(Type::Integer),
-            Expression::FloatLiteral(_) => Ok(Type::FloatingPoint),
-            Expression::BooleanLiteral(_) => Ok(Type::Boolean),
-            Expression::Variable(id) => match self.symbol_table.retrieve_type(id.clone()) {
-                Some(t) => Ok(t),
-                None => Err(format!("Use of undeclared variable {}", id.clone()))
+    //Returns Type::Error for invalid expressions, the reason is only reported where it originates
+    fn check_expression(&self, expr: Expression, diagnostics: &mut Vec<Diagnostic>) -> Type {
+        let span = expr.span;
+        match expr.kind {
+            ExpressionKind::IntegerLiteral(_) => Type::Integer,
+            ExpressionKind::FloatLiteral(_) => Type::FloatingPoint,
+            ExpressionKind::BooleanLiteral(_) => Type::Boolean,
+            ExpressionKind::StringLiteral(_) => Type::String,
+            ExpressionKind::Interpolation(parts) => {
+                for part in parts {
+                    let part_span = part.span;
+                    let part_type = self.check_expression(part, diagnostics);
+                    if part_type == Type::Unit {
+                        diagnostics.push(error(code::NOT_PRINTABLE,
+                                               format!("Cannot interpolate a value of type {} in a string", part_type),
+                                               part_span)
+                            .with_label("cannot be printed".to_string()));
+                    }
+                }
+                Type::String
+            }
+            ExpressionKind::Variable(id) => match self.symbol_table.retrieve_type(id.clone()) {
+                Some(t) => t,
+                None => {
+                    diagnostics.push(error(code::UNDECLARED_VARIABLE, format!("Use of undeclared variable {}", id), span)
+                        .with_label("not declared in this scope".to_string()));
+                    Type::Error
+                }
             },
-            Expression::BinaryOperation(left, op, right) => {
-                let left = result_propagate_failure_to_result!(self.analyze_expression(*left));
-                let right = result_propagate_failure_to_result!(self.analyze_expression(*right));
-                self.analyze_operator(left, op, right)
+            ExpressionKind::UnaryOperation(op, operand) => {
+                let constant = constant_value(&operand);
+                let operand = self.check_expression(*operand, diagnostics);
+                if operand == Type::Error {
+                    return Type::Error;
+                }
+                let valid = match op {
+                    UnaryOperator::Neg => operand == Type::Integer || operand == Type::FloatingPoint,
+                    UnaryOperator::Not => operand == Type::Boolean,
+                    UnaryOperator::BitNot => operand == Type::Integer
+                };
+                if !valid {
+                    diagnostics.push(error(code::INVALID_OPERANDS,
+                                           format!("Could not perform operator {} on {}", op, operand),
+                                           span)
+                        .with_label(format!("{}{}", op, operand)));
+                    return Type::Error;
+                }
+                if let Some(value) = constant {
+                    check_constant_operation(value.unary_operation(op), span, diagnostics);
+                }
+                operand
+            }
+            ExpressionKind::BinaryOperation(left, op, right) => {
+                let constants = (constant_value(&left), constant_value(&right));
+                let left = self.check_expression(*left, diagnostics);
+                let right = self.check_expression(*right, diagnostics);
+                if left == Type::Error || right == Type::Error {
+                    return Type::Error;
+                }
+                let label = format!("{} {} {}", left, op, right);
+                match self.analyze_operator(left, op, right) {
+                    Ok(t) => {
+                        if let (Some(left), Some(right)) = constants {
+                            check_constant_operation(left.binary_operation(op, right), span, diagnostics);
+                        }
+                        t
+                    }
+                    Err(msg) => {
+                        diagnostics.push(error(code::INVALID_OPERANDS, msg, span).with_label(label));
+                        Type::Error
+                    }
+                }
+            }
+            ExpressionKind::FunctionCall(id, arguments) => {
+                let signature = self.symbol_table.retrieve_function(id.clone());
+                if signature.is_none() {
+                    diagnostics.push(error(code::UNDECLARED_FUNCTION, format!("Call to undeclared function {}", id), span)
+                        .with_label("not declared".to_string()));
+                }
+                let argument_spans: Vec<Span> = arguments.iter().map(|argument| argument.span).collect();
+                let argument_types: Vec<Type> = arguments.into_iter()
+                    .map(|argument| self.check_expression(argument, diagnostics))
+                    .collect();
+                let signature = match signature {
+                    Some(signature) => signature,
+                    None => return Type::Error
+                };
+                if signature.parameters.len() != argument_types.len() {
+                    diagnostics.push(self.with_function_declaration(
+                        error(code::WRONG_ARGUMENT_COUNT,
+                              format!("Function {} takes {} arguments but {} were supplied",
+                                      id, signature.parameters.len(), argument_types.len()),
+                              span)
+                            .with_label(format!("expected {} arguments", signature.parameters.len())),
+                        id));
+                    return signature.return_type;
+                }
+                let arguments = signature.parameters.iter().zip(argument_types).zip(argument_spans);
+                for (position, ((p_type, a_type), argument_span)) in arguments.enumerate() {
+                    if !fits(p_type, &a_type) {
+                        diagnostics.push(self.with_function_declaration(
+                            error(code::MISMATCHED_TYPES,
+                                  format!("Mismatched types argument {} of function {} was declared {} but passed {}",
+                                          position + 1, id, p_type, a_type),
+                                  argument_span)
+                                .with_label(format!("expected {}, found {}", p_type, a_type)),
+                            id.clone()));
+                    }
+                }
+                signature.return_type
+            }
+            ExpressionKind::StructLiteral(id, fields) => {
+                let declared = self.symbol_table.retrieve_struct(&id);
+                if declared.is_none() {
+                    diagnostics.push(unknown_type(&id, span));
+                }
+                let mut given: Vec<String> = Vec::new();
+                for (field, expr) in fields {
+                    let expr_span = expr.span;
+                    let expr_type = self.check_expression(expr, diagnostics);
+                    let declared = match &declared {
+                        Some(declared) => declared,
+                        None => continue
+                    };
+                    if given.contains(&field) {
+                        diagnostics.push(error(code::ALREADY_DECLARED,
+                                               format!("Field {} of struct {} was given twice", field, id),
+                                               expr_span));
+                        continue;
+                    }
+                    given.push(field.clone());
+                    match declared.iter().find(|(_, name)| *name == field) {
+                        None => diagnostics.push(self.unknown_field(&id, &field, expr_span)),
+                        Some((f_type, _)) if !fits(f_type, &expr_type) =>
+                            diagnostics.push(error(code::MISMATCHED_TYPES,
+                                                   format!("Mismatched types field {} of struct {} was declared {} but given {}",
+                                                           field, id, f_type, expr_type),
+                                                   expr_span)
+                                .with_label(format!("expected {}, found {}", f_type, expr_type))),
+                        Some(_) => ()
+                    }
+                }
+                let declared = match declared {
+                    Some(declared) => declared,
+                    None => return Type::Error
+                };
+                let missing: Vec<String> = declared.into_iter()
+                    .map(|(_, field)| field)
+                    .filter(|field| !given.contains(field))
+                    .collect();
+                if !missing.is_empty() {
+                    diagnostics.push(error(code::MISSING_FIELD,
+                                           format!("Missing fields {} in the literal of struct {}", missing.join(", "), id),
+                                           span)
+                        .with_label("every field must be given a value".to_string()));
+                }
+                Type::Custom(id)
+            }
+            ExpressionKind::FieldAccess(target, field) => {
+                let target_type = self.check_expression(*target, diagnostics);
+                self.field_type(target_type, field, span, diagnostics)
+            }
+            ExpressionKind::EnumVariant(id, variant, values) => {
+                let payload = self.variant_payload(&id, &variant, span, diagnostics);
+                let value_spans: Vec<Span> = values.iter().map(|value| value.span).collect();
+                let value_types: Vec<Type> = values.into_iter()
+                    .map(|value| self.check_expression(value, diagnostics))
+                    .collect();
+                let payload = match payload {
+                    Some(payload) => payload,
+                    None if self.symbol_table.retrieve_enum(&id).is_some() => return Type::Custom(id),
+                    None => return Type::Error
+                };
+                if payload.len() != value_types.len() {
+                    diagnostics.push(error(code::WRONG_ARGUMENT_COUNT,
+                                           format!("Variant {}::{} holds {} values but {} were supplied",
+                                                   id, variant, payload.len(), value_types.len()),
+                                           span)
+                        .with_label(format!("expected {} values", payload.len())));
+                    return Type::Custom(id);
+                }
+                let values = payload.iter().zip(value_types).zip(value_spans);
+                for (position, ((p_type, v_type), value_span)) in values.enumerate() {
+                    if !fits(p_type, &v_type) {
+                        diagnostics.push(error(code::MISMATCHED_TYPES,
+                                               format!("Mismatched types value {} of variant {}::{} was declared {} but given {}",
+                                                       position + 1, id, variant, p_type, v_type),
+                                               value_span)
+                            .with_label(format!("expected {}, found {}", p_type, v_type)));
+                    }
+                }
+                Type::Custom(id)
+            }
+            ExpressionKind::Match(value, arms) => {
+                let value_type = self.check_expression(*value, diagnostics);
+                self.check_arms(&value_type, &arms, span, diagnostics);
+                //Every arm must give a value of the type of the first one
+                let mut result: Option<Type> = None;
+                for arm in arms {
+                    let bindings = self.check_pattern(&arm.pattern, &value_type, arm.span, diagnostics);
+                    let body_span = arm.body.span;
+                    let body_type = self.check_with_bindings(bindings, arm.body, arm.span, diagnostics);
+                    match &result {
+                        _ if body_type == Type::Error => (),
+                        None => result = Some(body_type),
+                        Some(expected) if !fits(expected, &body_type) =>
+                            diagnostics.push(error(code::MISMATCHED_TYPES,
+                                                   format!("Mismatched types arms of the match give {} and {}",
+                                                           expected, body_type),
+                                                   body_span)
+                                .with_label(format!("expected {}, found {}", expected, body_type))),
+                        Some(_) => ()
+                    }
+                }
+                result.unwrap_or(Type::Error)
+            }
+            ExpressionKind::Index(target, index) => {
+                let target_span = target.span;
+                let index_span = index.span;
+                let target = self.check_expression(*target, diagnostics);
+                let index = self.check_expression(*index, diagnostics);
+                element_type(target, index, target_span, index_span, diagnostics)
+            }
+            ExpressionKind::ArrayLiteral(elements) => {
+                //Every element refines the type of the others, like `[[], [1]]` which is an int[][]. An empty
+                //literal fits any array, a literal with an invalid element is itself invalid.
+                let mut element_type = Type::Error;
+                let mut invalid = false;
+                for element in elements {
+                    let element_span = element.span;
+                    let found = self.check_expression(element, diagnostics);
+                    invalid |= found == Type::Error;
+                    match unify(&element_type, &found) {
+                        Some(unified) => element_type = unified,
+                        None => diagnostics.push(error(code::MISMATCHED_TYPES,
+                                                       format!("Mismatched types the array has elements of type {} but also {}",
+                                                               element_type, found),
+                                                       element_span)
+                            .with_label(format!("expected {}, found {}", element_type, found)))
+                    }
+                }
+                if invalid { Type::Error } else { Type::Array(Box::new(element_type)) }
+            }
+            ExpressionKind::MapLiteral(entries) => {
+                //Like arrays, every entry refines the types of the others, an empty literal fits any map
+                let mut key_type = Type::Error;
+                let mut value_type = Type::Error;
+                let mut invalid = false;
+                for (key, value) in entries {
+                    let key_span = key.span;
+                    let value_span = value.span;
+                    let found_key = self.check_expression(key, diagnostics);
+                    let found_value = self.check_expression(value, diagnostics);
+                    invalid |= found_key == Type::Error || found_value == Type::Error;
+                    if key_type == Type::Error && !is_hashable(&found_key) {
+                        diagnostics.push(unhashable_key(&found_key, key_span));
+                    }
+                    match unify(&key_type, &found_key) {
+                        Some(unified) => key_type = unified,
+                        None => diagnostics.push(error(code::MISMATCHED_TYPES,
+                                                       format!("Mismatched types the map has keys of type {} but also {}",
+                                                               key_type, found_key),
+                                                       key_span)
+                            .with_label(format!("expected {}, found {}", key_type, found_key)))
+                    }
+                    match unify(&value_type, &found_value) {
+                        Some(unified) => value_type = unified,
+                        None => diagnostics.push(error(code::MISMATCHED_TYPES,
+                                                       format!("Mismatched types the map has values of type {} but also {}",
+                                                               value_type, found_value),
+                                                       value_span)
+                            .with_label(format!("expected {}, found {}", value_type, found_value)))
+                    }
+                }
+                if invalid { Type::Error } else { Type::Map(Box::new(key_type), Box::new(value_type)) }
             }
+            ExpressionKind::MethodCall(target, method, arguments) => {
+                let is_place = target.place().is_some();
+                let target = self.check_expression(*target, diagnostics);
+                let argument_spans: Vec<Span> = arguments.iter().map(|argument| argument.span).collect();
+                let argument_types: Vec<Type> = arguments.into_iter()
+                    .map(|argument| self.check_expression(argument, diagnostics))
+                    .collect();
+                if target == Type::Error {
+                    return Type::Error;
+                }
+                let (signature, changes_target) = match method_signature(&target, &method) {
+                    Some(signature) => signature,
+                    None => {
+                        diagnostics.push(error(code::UNKNOWN_METHOD, format!("Type {} has no method {}", target, method), span)
+                            .with_label("unknown method".to_string()));
+                        return Type::Error;
+                    }
+                };
+                if changes_target && !is_place {
+                    diagnostics.push(error(code::NOT_ASSIGNABLE,
+                                           format!("Method {} changes the {} it is called on, it must be called on a variable",
+                                                   method, target),
+                                           span)
+                        .with_label("not a variable".to_string())
+                        .with_help(format!("store the {} in a variable first", target)));
+                }
+                if signature.parameters.len() != argument_types.len() {
+                    diagnostics.push(error(code::WRONG_ARGUMENT_COUNT,
+                                           format!("Method {} takes {} arguments but {} were supplied",
+                                                   method, signature.parameters.len(), argument_types.len()),
+                                           span)
+                        .with_label(format!("expected {} arguments", signature.parameters.len())));
+                    return signature.return_type;
+                }
+                let arguments = signature.parameters.iter().zip(argument_types).zip(argument_spans);
+                for (position, ((p_type, a_type), argument_span)) in arguments.enumerate() {
+                    if !fits(p_type, &a_type) {
+                        diagnostics.push(error(code::MISMATCHED_TYPES,
+                                               format!("Mismatched types argument {} of method {} must be {} but is {}",
+                                                       position + 1, method, p_type, a_type),
+                                               argument_span)
+                            .with_label(format!("expected {}, found {}", p_type, a_type)));
+                    }
+                }
+                signature.return_type
+            }
+        }
+    }
+
+    //Types of the names bound by each iteration of a for loop: an Integer for a range, the element of an array
+    //or a String, the key and the value of a map. They are Error when the loop is invalid.
+    fn iterated_types(&self, iterable: Iterable, bindings: usize, span: Span, diagnostics: &mut Vec<Diagnostic>) -> Vec<Type> {
+        let types = match iterable {
+            Iterable::Range(start, end, _, step) => {
+                if step.as_deref().and_then(constant_value) == Some(Value::IntegerValue(0)) {
+                    diagnostics.push(error(code::ZERO_STEP, "The step of a range cannot be 0".to_string(), span)
+                        .with_label("the loop would never end".to_string()));
+                }
+                for bound in vec![Some(start), Some(end), step].into_iter().flatten().map(|bound| *bound) {
+                    let bound_span = bound.span;
+                    let bound_type = self.check_expression(bound, diagnostics);
+                    if !fits(&Type::Integer, &bound_type) {
+                        diagnostics.push(error(code::MISMATCHED_TYPES,
+                                               format!("Mismatched types the bounds and step of a range must be Integer but are {}",
+                                                       bound_type),
+                                               bound_span)
+                            .with_label(format!("expected Integer, found {}", bound_type)));
+                    }
+                }
+                vec![Type::Integer]
+            }
+            Iterable::Collection(collection) => {
+                let collection_span = collection.span;
+                match self.check_expression(collection, diagnostics) {
+                    Type::Error => vec![Type::Error; bindings],
+                    //The names would accept anything, nothing in the body could be checked
+                    found if !found.is_known() => {
+                        diagnostics.push(error(code::NOT_ITERABLE,
+                                               format!("Cannot iterate over {}, the type of its elements is unknown", found),
+                                               collection_span)
+                            .with_label("type of the elements is unknown".to_string())
+                            .with_help("declare the collection with its type first, like `int[] xs := [];`".to_string()));
+                        vec![Type::Error; bindings]
+                    }
+                    Type::String => vec![Type::String],
+                    Type::Array(element) => vec![*element],
+                    Type::Map(key, value) => vec![*key, *value],
+                    found => {
+                        diagnostics.push(error(code::NOT_ITERABLE, format!("Cannot iterate over a value of type {}", found),
+                                               collection_span)
+                            .with_label(format!("expected a range, String, array or map, found {}", found)));
+                        vec![Type::Error; bindings]
+                    }
+                }
+            }
+        };
+        if types.len() != bindings {
+            diagnostics.push(error(code::WRONG_ARGUMENT_COUNT,
+                                   format!("Each iteration gives {} values but the loop binds {}", types.len(), bindings),
+                                   span)
+                .with_label(format!("expected {} names", types.len()))
+                .with_help(if types.len() == 2 { "iterate over a map with `for (key, value) in ...`" } else {
+                    "bind a single name, like `for x in ...`"
+                }.to_string()));
+            //The bindings are still declared, so that their uses are not reported
+            return vec![Type::Error; bindings];
+        }
+        types
+    }
+
+    fn assigned_type(&self, id: String, span: Span, diagnostics: &mut Vec<Diagnostic>) -> Type {
+        self.symbol_table.retrieve_type(id.clone()).unwrap_or_else(|| {
+            diagnostics.push(error(code::UNDECLARED_VARIABLE,
+                                   format!("Cannot assign to undeclared variable {}", id),
+                                   span)
+                .with_label("not declared in this scope".to_string())
+                .with_help(format!("declare it together with its type, like `int {} := ...;`", id)));
+            Type::Error
+        })
+    }
+
+    //The conditions of if, while and for statements must be Boolean
+    fn check_condition(&self, cond: Expression, diagnostics: &mut Vec<Diagnostic>) {
+        let span = cond.span;
+        let cond_type = self.check_expression(cond, diagnostics);
+        if cond_type != Type::Boolean && cond_type != Type::Error {
+            diagnostics.push(error(code::MISMATCHED_TYPES, format!("Condition must be a Boolean but is {}", cond_type), span)
+                .with_label(format!("expected Boolean, found {}", cond_type)));
+        }
+    }
+
+    //Struct and enum types must have been declared, an unknown one is reported and becomes Error.
+    //The keys of a map must be hashable.
+    fn resolve_type(&self, d_type: Type, span: Span, diagnostics: &mut Vec<Diagnostic>) -> Type {
+        match d_type {
+            Type::Array(element) => Type::Array(Box::new(self.resolve_type(*element, span, diagnostics))),
+            Type::Map(key, value) => {
+                let key = self.resolve_type(*key, span, diagnostics);
+                if !is_hashable(&key) {
+                    diagnostics.push(unhashable_key(&key, span));
+                }
+                Type::Map(Box::new(key), Box::new(self.resolve_type(*value, span, diagnostics)))
+            }
+            Type::Custom(id) if !self.symbol_table.is_type_declared(&id) => {
+                diagnostics.push(unknown_type(&id, span));
+                Type::Error
+            }
+            d_type => d_type
+        }
+    }
+
+    fn field_type(&self, target: Type, field: String, span: Span, diagnostics: &mut Vec<Diagnostic>) -> Type {
+        let declared = match &target {
+            Type::Error => return Type::Error,
+            Type::Custom(id) => self.symbol_table.retrieve_struct(id),
+            _ => None
+        };
+        let declared = match declared {
+            Some(declared) => declared,
+            None => {
+                diagnostics.push(error(code::UNKNOWN_FIELD, format!("Type {} has no field {}", target, field), span)
+                    .with_label("only structs have fields".to_string()));
+                return Type::Error;
+            }
+        };
+        match declared.into_iter().find(|(_, name)| *name == field) {
+            Some((f_type, _)) => f_type,
+            None => {
+                diagnostics.push(self.unknown_field(&target.to_string(), &field, span));
+                Type::Error
+            }
+        }
+    }
+
+    //Types of the payload of a variant, None if the enum or the variant is unknown
+    fn variant_payload(&self, id: &str, variant: &str, span: Span, diagnostics: &mut Vec<Diagnostic>) -> Option<Vec<Type>> {
+        let variants = match self.symbol_table.retrieve_enum(id) {
+            Some(variants) => variants,
+            None => {
+                diagnostics.push(unknown_type(id, span));
+                return None;
+            }
+        };
+        let payload = variants.into_iter().find(|(name, _)| name == variant).map(|(_, payload)| payload);
+        if payload.is_none() {
+            let mut diagnostic = error(code::UNKNOWN_VARIANT, format!("Enum {} has no variant {}", id, variant), span)
+                .with_label("unknown variant".to_string());
+            if let Some(declaration) = self.symbol_table.retrieve_type_declaration(id) {
+                diagnostic = diagnostic.with_secondary(declaration, format!("enum `{}` declared here", id));
+            }
+            diagnostics.push(diagnostic);
+        }
+        payload
+    }
+
+    //Checks a pattern against the type of the matched value, returns the variables it binds
+    fn check_pattern(&self, pattern: &Pattern, value_type: &Type, span: Span,
+                     diagnostics: &mut Vec<Diagnostic>) -> Vec<(String, Type)> {
+        let (id, variant, bindings) = match pattern {
+            Pattern::CatchAll(binding) => return binding.iter().map(|binding| (binding.clone(), value_type.clone())).collect(),
+            Pattern::Variant(id, variant, bindings) => (id, variant, bindings)
+        };
+        let payload = self.variant_payload(id, variant, span, diagnostics);
+        if payload.is_some() && *value_type != Type::Error && *value_type != Type::Custom(id.clone()) {
+            diagnostics.push(error(code::MISMATCHED_TYPES,
+                                   format!("Mismatched types a pattern of enum {} cannot match a value of type {}",
+                                           id, value_type),
+                                   span)
+                .with_label(format!("expected {}, found {}", value_type, id)));
+        }
+        let payload = payload.unwrap_or_default();
+        if payload.len() != bindings.len() && self.symbol_table.retrieve_enum(id).is_some() {
+            diagnostics.push(error(code::WRONG_ARGUMENT_COUNT,
+                                   format!("Variant {}::{} holds {} values but the pattern has {}",
+                                           id, variant, payload.len(), bindings.len()),
+                                   span)
+                .with_label(format!("expected {} values", payload.len()))
+                .with_help("use `_` for the values that are not needed".to_string()));
+        }
+        //The bindings of an invalid pattern are still declared, so that their uses are not reported
+        bindings.iter().enumerate()
+            .filter_map(|(position, binding)| {
+                let b_type = payload.get(position).cloned().unwrap_or(Type::Error);
+                binding.clone().map(|binding| (binding, b_type))
+            })
+            .collect()
+    }
+
+    //Reports the arms that can never be reached, and the values accepted by no arm
+    fn check_arms<T>(&self, value_type: &Type, arms: &[MatchArm<T>], span: Span, diagnostics: &mut Vec<Diagnostic>) {
+        let variants: Option<Vec<String>> = match value_type {
+            Type::Custom(id) => self.symbol_table.retrieve_enum(id)
+                .map(|variants| variants.into_iter().map(|(variant, _)| variant).collect()),
+            _ => None
+        };
+        let mut catch_all = false;
+        let mut covered: Vec<&String> = Vec::new();
+        for arm in arms {
+            let every_variant = variants.as_ref().is_some_and(|variants| variants.iter().all(|v| covered.contains(&v)));
+            let reachable = !catch_all && match &arm.pattern {
+                Pattern::CatchAll(_) => !every_variant,
+                Pattern::Variant(_, variant, _) => !covered.contains(&variant)
+            };
+            if !reachable {
+                diagnostics.push(error(code::UNREACHABLE_ARM,
+                                       "Unreachable arm, the arms before it accept every value it matches".to_string(),
+                                       arm.span)
+                    .with_label("unreachable arm".to_string())
+                    .with_help("remove the arm or move it before the arms that shadow it".to_string()));
+            }
+            match &arm.pattern {
+                Pattern::CatchAll(_) => catch_all = true,
+                Pattern::Variant(_, variant, _) => covered.push(variant)
+            }
+        }
+        if catch_all || *value_type == Type::Error {
+            return;
+        }
+        let missing: Vec<String> = match variants {
+            Some(variants) => variants.into_iter()
+                .filter(|variant| !covered.contains(&variant))
+                .map(|variant| format!("{}::{}", value_type, variant))
+                .collect(),
+            None => {
+                diagnostics.push(error(code::NON_EXHAUSTIVE_MATCH,
+                                       format!("Match on a value of type {} must end with a `_` arm", value_type),
+                                       span)
+                    .with_label("not every value is handled".to_string())
+                    .with_help("only enums can be matched without a `_` arm".to_string()));
+                return;
+            }
+        };
+        if !missing.is_empty() {
+            diagnostics.push(error(code::NON_EXHAUSTIVE_MATCH,
+                                   format!("Match on {} does not handle {}", value_type, missing.join(", ")),
+                                   span)
+                .with_label("not every variant is handled".to_string())
+                .with_help("add an arm for each of them, or a `_` arm".to_string()));
+        }
+    }
+
+    fn declare_bindings(&mut self, bindings: Vec<(String, Type)>, span: Span, diagnostics: &mut Vec<Diagnostic>) {
+        for (binding, b_type) in bindings {
+            if !self.symbol_table.declare(binding.clone(), b_type, span) {
+                diagnostics.push(self.already_declared(binding, span));
+            }
+        }
+    }
+
+    //Expressions are checked without changing the analyzer, the bindings of an arm are declared in a copy of it
+    fn check_with_bindings(&self, bindings: Vec<(String, Type)>, expr: Expression, span: Span,
+                           diagnostics: &mut Vec<Diagnostic>) -> Type {
+        if bindings.is_empty() {
+            return self.check_expression(expr, diagnostics);
+        }
+        let mut scope = self.clone();
+        scope.symbol_table.create_frame();
+        scope.declare_bindings(bindings, span, diagnostics);
+        scope.check_expression(expr, diagnostics)
+    }
+
+    fn unknown_field(&self, id: &str, field: &str, span: Span) -> Diagnostic {
+        let mut diagnostic = error(code::UNKNOWN_FIELD, format!("Struct {} has no field {}", id, field), span)
+            .with_label("unknown field".to_string());
+        if let Some(declaration) = self.symbol_table.retrieve_type_declaration(id) {
+            diagnostic = diagnostic.with_secondary(declaration, format!("struct `{}` declared here", id));
+        }
+        diagnostic
+    }
+
+    fn check_loop_control(&self, keyword: &str, label: Option<String>, span: Span, diagnostics: &mut Vec<Diagnostic>) {
+        if self.loops.is_empty() {
+            diagnostics.push(error(code::OUTSIDE_LOOP, format!("Cannot {} outside of a loop", keyword), span)
+                .with_label(format!("`{}` outside of a loop", keyword)));
+        } else if let Some(label) = label {
+            if !self.loops.contains(&Some(label.clone())) {
+                diagnostics.push(error(code::UNDECLARED_LABEL, format!("Use of undeclared label {}", label), span)
+                    .with_label("no enclosing loop has this label".to_string()));
+            }
+        }
+    }
+
+    fn already_declared(&self, id: String, span: Span) -> Diagnostic {
+        let mut diagnostic = error(code::ALREADY_DECLARED,
+                                   format!("Cannot declare variable {} because it was already declared in this scope", id),
+                                   span);
+        if let Some(declaration) = self.symbol_table.retrieve_declaration(id) {
+            diagnostic = diagnostic.with_secondary(declaration, "previously declared here".to_string());
+        }
+        diagnostic
+    }
+
+    fn with_function_declaration(&self, diagnostic: Diagnostic, id: String) -> Diagnostic {
+        match self.symbol_table.retrieve_function_declaration(id.clone()) {
+            Some(declaration) => diagnostic.with_secondary(declaration, format!("function `{}` declared here", id)),
+            None => diagnostic
         }
     }
 
     fn analyze_operator(&self, l_type: Type, op: Operator, r_type: Type) -> Result<Type, String> {
-        if l_type == Type::Unit || l_type.is_custom() {
+        if l_type == Type::Unit || l_type.is_custom() || l_type.is_collection() {
             return Err(format!("Left operand cannot be subject of operator {}", op));
         }
-        if r_type == Type::Unit || r_type.is_custom() {
+        if r_type == Type::Unit || r_type.is_custom() || r_type.is_collection() {
             return Err(format!("Right operand cannot be subject of operator {}", op));
         }
         if r_type != l_type {
             return Err(format!("Unmatched values ({}, {}) in binary operator {}", l_type, r_type, op));
         }
         match op {
-            Operator::Eq | Operator::Gt | Operator::Lt => Ok(Type::Boolean),
-            Operator::And | Operator::Or => {
-                if l_type == Type::FloatingPoint {
-                    Err(format!("Could not perform bitwise  operations on floats"))
-                } else {
-                    Ok(l_type)
-                }
-            }
-            Operator::Pow => Ok(l_type),
-            Operator::Add | Operator::Sub | Operator::Mul | Operator::Div => {
-                if l_type == Type::Boolean {
-                    Err(format!("Could not perform mathematical operations on boolean"))
-                } else {
-                    Ok(l_type)
-                }
+            Operator::Eq | Operator::Ne => Ok(Type::Boolean),
+            //Only numbers have an order
+            Operator::Gt | Operator::Ge | Operator::Lt | Operator::Le => match l_type {
+                Type::Integer | Type::FloatingPoint => Ok(Type::Boolean),
+                _ => Err(format!("Could not perform operator {} on {}, only numbers can be ordered", op, l_type))
+            },
+            Operator::BitAnd | Operator::BitOr | Operator::BitXor | Operator::Shl | Operator::Shr => match l_type {
+                Type::Integer => Ok(l_type),
+                Type::Boolean => Err(match op {
+                    Operator::BitAnd => "Could not perform bitwise operations on booleans, use && instead".to_string(),
+                    Operator::BitOr => "Could not perform bitwise operations on booleans, use || instead".to_string(),
+                    Operator::BitXor => "Could not perform bitwise operations on booleans, use != instead".to_string(),
+                    _ => "Could not perform bitwise operations on booleans".to_string()
+                }),
+                _ => Err(format!("Could not perform bitwise operations on {}", l_type))
+            },
+            Operator::And | Operator::Or => match l_type {
+                Type::Boolean => Ok(l_type),
+                _ => Err(format!("Could not perform logical operator {} on {}", op, l_type))
+            },
+            //Strings can only be concatenated
+            Operator::Add if l_type == Type::String => Ok(l_type),
+            Operator::Add | Operator::Sub | Operator::Mul | Operator::Div | Operator::Rem | Operator::Pow => match l_type {
+                Type::Boolean => Err("Could not perform mathematical operations on boolean".to_string()),
+                Type::String => Err(format!("Could not perform operator {} on strings", op)),
+                _ => Ok(l_type)
             }
         }
     }
 }
+
+fn error(code: &'static str, message: String, span: Span) -> Diagnostic {
+    Diagnostic::error(Phase::Analysis, code, message, Some(span))
+}
+
+//Whether a value of type `found` can be stored where `expected` is declared. Error fits anything, so that an
+//invalid expression is only reported where it originates, and the empty literals fit any array or map.
+fn fits(expected: &Type, found: &Type) -> bool {
+    match (expected, found) {
+        (Type::Error, _) | (_, Type::Error) => true,
+        (Type::Array(expected), Type::Array(found)) => fits(expected, found),
+        (Type::Map(expected_key, expected_value), Type::Map(found_key, found_value)) =>
+            fits(expected_key, found_key) && fits(expected_value, found_value),
+        (expected, found) => expected == found
+    }
+}
+
+//Type of `target[index]`, strings are indexed by character
+fn element_type(target: Type, index: Type, target_span: Span, index_span: Span, diagnostics: &mut Vec<Diagnostic>) -> Type {
+    if target == Type::Error || index == Type::Error {
+        return Type::Error;
+    }
+    let element = match target {
+        Type::String => Type::String,
+        Type::Array(element) => *element,
+        target => {
+            diagnostics.push(error(code::INVALID_OPERANDS, format!("Cannot index a value of type {}", target), target_span)
+                .with_label(format!("expected String or an array, found {}", target)));
+            return Type::Error;
+        }
+    };
+    if index != Type::Integer {
+        diagnostics.push(error(code::MISMATCHED_TYPES, format!("Index must be an Integer but is {}", index), index_span)
+            .with_label(format!("expected Integer, found {}", index)));
+    }
+    element
+}
+
+//Signature of a method of the collections, and whether it changes the collection it is called on
+fn method_signature(target: &Type, method: &str) -> Option<(FunctionSignature, bool)> {
+    let (parameters, return_type, changes_target) = match (target, method) {
+        //Strings are measured by character
+        (Type::String, "len") => (vec![], Type::Integer, false),
+        (Type::Array(_), "len") => (vec![], Type::Integer, false),
+        (Type::Array(element), "push") => (vec![(**element).clone()], Type::Unit, true),
+        (Type::Array(element), "pop") => (vec![], (**element).clone(), true),
+        (Type::Map(_, _), "len") => (vec![], Type::Integer, false),
+        (Type::Map(key, value), "insert") => (vec![(**key).clone(), (**value).clone()], Type::Unit, true),
+        (Type::Map(key, value), "get") => (vec![(**key).clone()], (**value).clone(), false),
+        //Tells whether the key was in the map
+        (Type::Map(key, _), "remove") => (vec![(**key).clone()], Type::Boolean, true),
+        (Type::Map(key, _), "contains") => (vec![(**key).clone()], Type::Boolean, false),
+        //In the order of the keys
+        (Type::Map(key, _), "keys") => (vec![], Type::Array(key.clone()), false),
+        (Type::Map(_, value), "values") => (vec![], Type::Array(value.clone()), false),
+        _ => return None
+    };
+    Some((FunctionSignature { parameters, return_type }, changes_target))
+}
+
+//Most precise type fitting both `a` and `b`, the unknown parts of one are taken from the other. None if they
+//do not fit each other.
+fn unify(a: &Type, b: &Type) -> Option<Type> {
+    match (a, b) {
+        (Type::Error, other) | (other, Type::Error) => Some(other.clone()),
+        (Type::Array(a), Type::Array(b)) => Some(Type::Array(Box::new(unify(a, b)?))),
+        (Type::Map(a_key, a_value), Type::Map(b_key, b_value)) =>
+            Some(Type::Map(Box::new(unify(a_key, b_key)?), Box::new(unify(a_value, b_value)?))),
+        (a, b) if a == b => Some(a.clone()),
+        _ => None
+    }
+}
+
+//Types that can be the key of a map
+fn is_hashable(key: &Type) -> bool {
+    matches!(key, Type::Integer | Type::Boolean | Type::String | Type::Error)
+}
+
+fn unhashable_key(key: &Type, span: Span) -> Diagnostic {
+    error(code::UNHASHABLE_KEY, format!("Type {} cannot be the key of a map", key), span)
+        .with_label(format!("{} is not hashable", key))
+        .with_help("keys can be int, bool or string".to_string())
+}
+
+fn unknown_type(id: &str, span: Span) -> Diagnostic {
+    error(code::UNKNOWN_TYPE, format!("Unknown type {}", id), span)
+        .with_label("not declared".to_string())
+        .with_help(format!("declare it before its first use, like `struct {} {{ int field }}`", id))
+}
+
+//Value of an expression made only of literals, None if it is not constant or cannot be evaluated
+fn constant_value(expr: &Expression) -> Option<Value> {
+    match &expr.kind {
+        ExpressionKind::IntegerLiteral(val) => Some(Value::IntegerValue(*val)),
+        ExpressionKind::FloatLiteral(val) => Some(Value::FloatValue(*val)),
+        ExpressionKind::BooleanLiteral(val) => Some(Value::BooleanValue(*val)),
+        ExpressionKind::UnaryOperation(op, operand) => constant_value(operand)?.unary_operation(*op).ok(),
+        ExpressionKind::BinaryOperation(left, op, right) =>
+            constant_value(left)?.binary_operation(*op, constant_value(right)?).ok(),
+        _ => None
+    }
+}
+
+//Constant operations are folded with the same arithmetic as the interpreter, so an overflow that
+//would always happen at runtime is reported before the program runs
+fn check_constant_operation(result: Result<Value, RuntimeError>, span: Span, diagnostics: &mut Vec<Diagnostic>) {
+    if let Err(failure) = result {
+        if failure.code == code::INTEGER_OVERFLOW {
+            diagnostics.push(error(code::CONSTANT_OVERFLOW, failure.message, span)
+                .with_label("does not fit in an Integer".to_string())
+                .with_help("use the wrapping_* or saturating_* functions if the overflow is intended".to_string()));
+        }
+    }
+}
+
+//Conservative check, a block returns if any of its statements always returns
+fn always_returns(ast: &AST) -> bool {
+    match &ast.kind {
+        ASTKind::Return(_) => true,
+        ASTKind::Block(nodes) => nodes.iter().any(always_returns),
+        ASTKind::IfStatement(_, then, Some(otherwise)) => always_returns(then) && always_returns(otherwise),
+        //A match that does not handle every value is reported on its own
+        ASTKind::Match(_, arms) => !arms.is_empty() && arms.iter().all(|arm| always_returns(&arm.body)),
+        _ => false
+    }
+}
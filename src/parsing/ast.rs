@@ -1,21 +1,41 @@
 use std::fmt::{Display, Formatter, Result};
 
-use crate::parsing::token::Operator;
+use crate::diagnostic::Span;
+use crate::parsing::token::{Operator, UnaryOperator};
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum Type {
     Integer,
     FloatingPoint,
     Unit,
     Boolean,
+    String,
+    //Struct or enum declared in the program, by name
     Custom(String),
+    //Growable array of elements of the boxed type
+    Array(Box<Type>),
+    //Key and value types, the keys are kept sorted
+    Map(Box<Type>, Box<Type>),
+    //Type of the expressions that failed the analysis, operations on it are not reported again
+    Error,
 }
 
 impl Type {
     pub fn is_custom(&self) -> bool {
+        matches!(self, Type::Custom(_))
+    }
+
+    pub fn is_collection(&self) -> bool {
+        matches!(self, Type::Array(_) | Type::Map(_, _))
+    }
+
+    //False for the types of the empty literals and of the invalid expressions, which fit any other type
+    pub fn is_known(&self) -> bool {
         match self {
-            Type::Custom(_) => true,
-            _ => false
+            Type::Array(element) => element.is_known(),
+            Type::Map(key, value) => key.is_known() && value.is_known(),
+            Type::Error => false,
+            _ => true
         }
     }
 }
@@ -27,28 +47,160 @@ impl Display for Type {
             Type::FloatingPoint => write!(f, "FloatingPoint"),
             Type::Unit => write!(f, "Unit"),
             Type::Boolean => write!(f, "Boolean"),
-            Type::Custom(_) => write!(f, "Custom")
+            Type::String => write!(f, "String"),
+            Type::Custom(name) => write!(f, "{}", name),
+            //Type of the empty array literal
+            Type::Array(element) if **element == Type::Error => write!(f, "[]"),
+            Type::Array(element) => write!(f, "{}[]", element),
+            //Type of the empty map literal
+            Type::Map(key, value) if **key == Type::Error && **value == Type::Error => write!(f, "[:]"),
+            Type::Map(key, value) => write!(f, "map<{}, {}>", key, value),
+            Type::Error => write!(f, "{{error}}")
         }
     }
 }
 
 #[derive(Clone)]
-pub enum Expression {
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
+impl Expression {
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Self {
+            kind,
+            span,
+        }
+    }
+
+    //Variable and path designated by the expression, None if it is not a part of a variable like `p.xs[0]`
+    pub fn place(&self) -> Option<(String, Vec<Accessor>)> {
+        match &self.kind {
+            ExpressionKind::Variable(id) => Some((id.clone(), Vec::new())),
+            ExpressionKind::FieldAccess(target, field) => {
+                let (id, mut path) = target.place()?;
+                path.push(Accessor::Field(field.clone()));
+                Some((id, path))
+            }
+            ExpressionKind::Index(target, index) => {
+                let (id, mut path) = target.place()?;
+                path.push(Accessor::Index((**index).clone()));
+                Some((id, path))
+            }
+            _ => None
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum ExpressionKind {
     IntegerLiteral(i32),
     FloatLiteral(f32),
     BooleanLiteral(bool),
+    StringLiteral(String),
+    //Parts of an interpolated string, each one is printed and concatenated
+    Interpolation(Vec<Expression>),
+    UnaryOperation(UnaryOperator, Box<Expression>),
     BinaryOperation(Box<Expression>, Operator, Box<Expression>),
     Variable(String),
+    FunctionCall(String, Vec<Expression>),
+    //Struct name and the value of each field, in the order they were written
+    StructLiteral(String, Vec<(String, Expression)>),
+    FieldAccess(Box<Expression>, String),
+    //Enum name, variant and the values of its payload
+    EnumVariant(String, String, Vec<Expression>),
+    //The arms are tried in order, the first one accepting the value gives the result
+    Match(Box<Expression>, Vec<MatchArm<Expression>>),
+    //Target and index, strings are indexed by character
+    Index(Box<Expression>, Box<Expression>),
+    ArrayLiteral(Vec<Expression>),
+    MapLiteral(Vec<(Expression, Expression)>),
+    //Target, method and arguments. The methods changing their target are applied to the variable it designates.
+    MethodCall(Box<Expression>, String, Vec<Expression>),
 }
 
 #[derive(Clone)]
-pub enum AST {
+pub struct FunctionSignature {
+    pub parameters: Vec<Type>,
+    pub return_type: Type,
+}
+
+//Compound statements span only their header, their blocks carry their own spans
+#[derive(Clone)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct AST {
+    pub kind: ASTKind,
+    pub span: Span,
+}
+
+impl AST {
+    pub fn new(kind: ASTKind, span: Span) -> Self {
+        Self {
+            kind,
+            span,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum ASTKind {
     Block(Vec<AST>),
     Assign(String, Expression),
+    //`x += 1` applies the operator to the current value of x and the expression
+    CompoundAssign(String, Operator, Expression),
+    //Assignment to a part of a variable like `p.x := 1;` or `xs[0] := 1;`, compound when an operator is given like `p.x += 1;`
+    MemberAssign(String, Vec<Accessor>, Option<Operator>, Expression),
     VarDeclaration(Type, String),
     Print(Expression),
     VarDeclarationAndAssignment(Type, String, Expression),
-    IfStatement(Expression, Box<AST>),
-    WhileStatement(Expression, Box<AST>),
-    ForStatement(Box<AST>, Expression, Box<AST>, Box<AST>),
-}
\ No newline at end of file
+    //Condition, then branch and the optional else branch, an else if is an IfStatement there
+    IfStatement(Expression, Box<AST>, Option<Box<AST>>),
+    //Loops start with their optional label
+    WhileStatement(Option<String>, Expression, Box<AST>),
+    ForStatement(Option<String>, Box<AST>, Expression, Box<AST>, Box<AST>),
+    //`for x in ...` or `for (k, v) in ...`, the names are None for a `_`
+    ForInStatement(Option<String>, Vec<Option<String>>, Iterable, Box<AST>),
+    //Label of the loop to leave or continue, the innermost one if None
+    Break(Option<String>),
+    Continue(Option<String>),
+    FunctionDeclaration(String, Vec<(Type, String)>, Type, Box<AST>),
+    StructDeclaration(String, Vec<(Type, String)>),
+    //Name of each variant with the types of its payload
+    EnumDeclaration(String, Vec<(String, Vec<Type>)>),
+    //Like the match expression, but each arm runs a block
+    Match(Expression, Vec<MatchArm<AST>>),
+    Return(Option<Expression>),
+    ExpressionStatement(Expression),
+}
+
+//Step from a value to one of its parts in an assignment target
+#[derive(Clone)]
+pub enum Accessor {
+    Field(String),
+    Index(Expression),
+}
+
+#[derive(Clone)]
+pub struct MatchArm<T> {
+    pub pattern: Pattern,
+    //Span of the pattern
+    pub span: Span,
+    pub body: T,
+}
+
+#[derive(Clone)]
+pub enum Iterable {
+    //Start, end, whether the end is included and the optional step
+    Range(Box<Expression>, Box<Expression>, bool, Option<Box<Expression>>),
+    //Array or map
+    Collection(Expression),
+}
+
+#[derive(Clone)]
+pub enum Pattern {
+    //`_` or a name bound to the whole value, accepts any value
+    CatchAll(Option<String>),
+    //Enum, variant and the names bound to the values of its payload, None for a `_`
+    Variant(String, String, Vec<Option<String>>),
+}
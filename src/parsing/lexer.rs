@@ -4,18 +4,29 @@ use std::str::Chars;
 
 use lazy_static::lazy_static;
 
-use crate::parsing::token::{Operator, Token, TokenType};
+use crate::diagnostic::{code, Diagnostic, Phase, Position, Span};
+use crate::parsing::token::{Operator, StringSegment, Token, TokenType, UnaryOperator};
+use crate::result_propagate_failure_to_result;
 
 lazy_static! {
     static ref RESERVED_KEYWORDS: HashMap<&'static str, TokenType> = {
         let mut m = HashMap::new();
         m.insert("Fn", TokenType::Fn);
+        m.insert("struct", TokenType::Struct);
+        m.insert("enum", TokenType::Enum);
         m.insert("true", TokenType::BooleanLiteral(true));
         m.insert("false", TokenType::BooleanLiteral(false));
         m.insert("if", TokenType::If);
+        m.insert("else", TokenType::Else);
+        m.insert("match", TokenType::Match);
         m.insert("while", TokenType::While);
         m.insert("for", TokenType::For);
+        m.insert("in", TokenType::In);
+        m.insert("step", TokenType::Step);
+        m.insert("break", TokenType::Break);
+        m.insert("continue", TokenType::Continue);
         m.insert("print", TokenType::Print);
+        m.insert("return", TokenType::Return);
         m
     };
 }
@@ -23,129 +34,385 @@ lazy_static! {
 
 pub struct Lexer<'a> {
     chars: Peekable<Chars<'a>>,
-    current_line: u32,
-    current_char: u32,
+    position: Position,
+    warnings: Vec<Diagnostic>,
 }
 
 impl<'a> Lexer<'a> {
-    pub fn new(input: &'a String) -> Self {
+    pub fn new(input: &'a str) -> Self {
         Self {
             chars: input.chars().peekable(),
-            current_line: 0,
-            current_char: 0,
+            position: Position::start(),
+            warnings: Vec::new(),
         }
     }
 }
 
 impl Lexer<'_> {
-    pub fn lex(&mut self) -> Result<Vec<Token>, String> {
+    pub fn lex(&mut self) -> Result<Vec<Token>, Diagnostic> {
         let mut lexeems = Vec::new();
-        loop {
-            match self.next() {
-                Ok(token) => lexeems.push(token),
-                Err(err) => return if err == "No tokens left to parse" { Ok(lexeems) } else { Err(err) }
+        while let Some(token) = self.next() {
+            lexeems.push(result_propagate_failure_to_result!(token));
+        }
+        Ok(lexeems)
+    }
+
+    //Deprecated syntax found while lexing, it does not prevent the source from being lexed
+    pub fn warnings(self) -> Vec<Diagnostic> {
+        self.warnings
+    }
+
+    fn next(&mut self) -> Option<Result<Token, Diagnostic>> {
+        let start = self.position;
+        let next_char = *self.chars.peek()?;
+        let token = match next_char {
+            ' ' | '\t' | '\r' | '\n' => {
+                self.skip_char();
+                return self.next();
             }
+            ',' => self.skip_and_construct(TokenType::Comma, start),
+            '.' => self.next_dot(start),
+            ';' => self.skip_and_construct(TokenType::Semicolon, start),
+            '{' => self.skip_and_construct(TokenType::LCurlyBracket, start),
+            '}' => self.skip_and_construct(TokenType::RCurlyBracket, start),
+            '(' => self.skip_and_construct(TokenType::LRoundBracket, start),
+            ')' => self.skip_and_construct(TokenType::RRoundBracket, start),
+            '[' => self.skip_and_construct(TokenType::LSquareBracket, start),
+            ']' => self.skip_and_construct(TokenType::RSquareBracket, start),
+            '"' => self.next_string(start),
+            '>' => self.next_comparison(Operator::Gt, Operator::Ge, Operator::Shr, start),
+            '<' => self.next_comparison(Operator::Lt, Operator::Le, Operator::Shl, start),
+            '=' => self.next_equality(start),
+            '+' => self.skip_and_construct(TokenType::Operator(Operator::Add, 7, true), start),
+            '-' => self.skip_and_construct(TokenType::Operator(Operator::Sub, 7, true), start),
+            '&' => self.next_doubled(TokenType::Operator(Operator::BitAnd, 5, true),
+                                     TokenType::Operator(Operator::And, 1, true), start),
+            '^' => self.skip_and_construct(TokenType::Operator(Operator::BitXor, 4, true), start),
+            '|' => self.next_doubled(TokenType::Operator(Operator::BitOr, 3, true),
+                                     TokenType::Operator(Operator::Or, 0, true), start),
+            //The power is right associative, 2 ** 3 ** 2 is 2 ** 9
+            '*' => self.next_doubled(TokenType::Operator(Operator::Mul, 8, true),
+                                     TokenType::Operator(Operator::Pow, 10, false), start),
+            '%' => self.skip_and_construct(TokenType::Operator(Operator::Rem, 8, true), start),
+            '/' => {
+                self.skip_char();
+                match self.chars.peek() {
+                    Some('/') => {
+                        self.skip_line_comment();
+                        return self.next();
+                    }
+                    Some('*') => {
+                        if let Err(error) = self.skip_block_comment(start) {
+                            return Some(Err(error));
+                        }
+                        return self.next();
+                    }
+                    _ => self.construct_token(TokenType::Operator(Operator::Div, 8, true), start)
+                }
+            }
+            '!' => {
+                self.skip_char();
+                match self.chars.peek() {
+                    Some('=') => self.skip_and_construct(TokenType::Operator(Operator::Ne, 2, false), start),
+                    _ => self.construct_token(TokenType::UnaryOperator(UnaryOperator::Not), start)
+                }
+            }
+            '~' => self.skip_and_construct(TokenType::UnaryOperator(UnaryOperator::BitNot), start),
+            ':' => self.next_assignment(start),
+            '0'..='9' => self.next_number(start),
+            'a'..='z' | 'A'..='Z' | '_' => self.next_id(start),
+            unexpected => {
+                self.skip_char();
+                Err(self.error(code::UNEXPECTED_CHARACTER, format!("Unexpected character '{}'", unexpected), start))
+            }
+        };
+        Some(token.map(|token| self.compound_assignment(token)))
+    }
+
+    //The leading '/' was already consumed, the newline is left to the whitespace handling
+    fn skip_line_comment(&mut self) {
+        while self.chars.peek().is_some_and(|c| *c != '\n') {
+            self.skip_char();
         }
     }
 
-    fn next(&mut self) -> Result<Token, String> {
-        match self.chars.peek() {
-            None => Err("No tokens left to parse".to_string()),
-            Some(next_char) => {
-                match next_char {
-                    ' ' | '\t' | '\n' => {
-                        self.skip_char();
-                        self.next()
+    //The leading '/' was already consumed. Block comments nest, each '/*' must be closed by its own '*/'
+    fn skip_block_comment(&mut self, start: Position) -> Result<(), Diagnostic> {
+        self.skip_char();
+        let mut openings = vec![Span::new(start, self.position)];
+        while let Some(&current) = self.chars.peek() {
+            let position = self.position;
+            self.skip_char();
+            match (current, self.chars.peek()) {
+                ('*', Some('/')) => {
+                    self.skip_char();
+                    openings.pop();
+                    if openings.is_empty() {
+                        return Ok(());
                     }
-                    ',' => self.skip_and_construct(TokenType::Comma),
-                    ';' => self.skip_and_construct(TokenType::Semicolon),
-                    '{' => self.skip_and_construct(TokenType::LCurlyBracket),
-                    '}' => self.skip_and_construct(TokenType::RCurlyBracket),
-                    '(' => self.skip_and_construct(TokenType::LRoundBracket),
-                    ')' => self.skip_and_construct(TokenType::RRoundBracket),
-                    '>' => self.skip_and_construct(TokenType::Operator(Operator::Gt, 0, false)),
-                    '<' => self.skip_and_construct(TokenType::Operator(Operator::Lt, 0, false)),
-                    '=' => self.skip_and_construct(TokenType::Operator(Operator::Eq, 0, false)),
-                    '+' => self.skip_and_construct(TokenType::Operator(Operator::Add, 1, true)),
-                    '-' => self.skip_and_construct(TokenType::Operator(Operator::Sub, 1, true)),
-                    '&' => self.skip_and_construct(TokenType::Operator(Operator::And, 1, true)),
-                    '|' => self.skip_and_construct(TokenType::Operator(Operator::Or, 1, true)),
-                    '*' => self.skip_and_construct(TokenType::Operator(Operator::Mul, 2, true)),
-                    '/' => self.skip_and_construct(TokenType::Operator(Operator::Div, 2, true)),
-                    '^' => self.skip_and_construct(TokenType::Operator(Operator::Pow, 3, true)),
-                    ':' => self.next_assignment(),
-                    '0'..='9' => self.next_number(),
-                    'a'..='z' | 'A'..='Z' => self.next_id(),
-                    unexpected => Err(format!("Unexpected character '{}' at line {} char {}",
-                                              unexpected, self.current_line, self.current_char))
                 }
+                ('/', Some('*')) => {
+                    self.skip_char();
+                    openings.push(Span::new(position, self.position));
+                }
+                _ => ()
             }
         }
+        let mut diagnostic = Diagnostic::error(Phase::Lexing, code::UNTERMINATED_COMMENT,
+                                               "Unterminated block comment".to_string(), Some(openings[0]))
+            .with_label("comment opened here is never closed".to_string());
+        for nested in openings.iter().skip(1) {
+            diagnostic = diagnostic.with_secondary(*nested, "nested comment, also needs its own '*/'".to_string());
+        }
+        Err(diagnostic)
     }
 
-    fn next_id(&mut self) -> Result<Token, String> {
+    fn next_id(&mut self, start: Position) -> Result<Token, Diagnostic> {
         let mut identifier = "".to_string();
-        while self.chars.peek().map_or(false, |c| c.is_alphabetic() || c.is_numeric()) {
-            self.current_char += 1;
-            identifier.push(self.chars.next().unwrap());
+        while self.chars.peek().is_some_and(|c| c.is_alphanumeric() || *c == '_') {
+            identifier.push(self.skip_char());
         }
         match RESERVED_KEYWORDS.get(identifier.as_str()) {
-            Some(token_type) => self.construct_token(token_type.clone()),
-            None => self.construct_token(TokenType::Id(identifier))
+            Some(token_type) => self.construct_token(token_type.clone(), start),
+            None => self.construct_token(TokenType::Id(identifier), start)
+        }
+    }
+
+    fn next_string(&mut self, start: Position) -> Result<Token, Diagnostic> {
+        self.skip_char();
+        let opening = Span::new(start, self.position);
+        let mut string = String::new();
+        let mut segments = Vec::new();
+        loop {
+            match self.chars.peek() {
+                Some('"') if segments.is_empty() => return self.skip_and_construct(TokenType::StringLiteral(string), start),
+                Some('"') => {
+                    if !string.is_empty() {
+                        segments.push(StringSegment::Text(string));
+                    }
+                    return self.skip_and_construct(TokenType::InterpolatedString(segments), start);
+                }
+                Some('{') => {
+                    if !string.is_empty() {
+                        segments.push(StringSegment::Text(std::mem::take(&mut string)));
+                    }
+                    segments.push(StringSegment::Code(result_propagate_failure_to_result!(self.next_interpolation())));
+                }
+                Some('\\') => string.push(result_propagate_failure_to_result!(self.next_escape())),
+                Some(_) => string.push(self.skip_char()),
+                None => return Err(Diagnostic::error(Phase::Lexing, code::UNTERMINATED_STRING,
+                                                     "Unterminated string literal".to_string(), Some(opening))
+                    .with_label("string opened here is never closed".to_string()))
+            }
+        }
+    }
+
+    //Lexes the expression embedded in a string up to its closing '}', which may contain strings itself
+    fn next_interpolation(&mut self) -> Result<Vec<Token>, Diagnostic> {
+        let start = self.position;
+        self.skip_char();
+        let opening = Span::new(start, self.position);
+        let unterminated = || Diagnostic::error(Phase::Lexing, code::UNTERMINATED_INTERPOLATION,
+                                                "Unterminated interpolation in string literal".to_string(), Some(opening))
+            .with_label("'{' opened here is never closed".to_string());
+        let mut tokens = Vec::new();
+        let mut depth = 0;
+        loop {
+            let token = match self.next() {
+                Some(Ok(token)) => token,
+                //Without its '}', the quote meant to close the string opens a new one inside the interpolation
+                Some(Err(error)) if error.code == code::UNTERMINATED_STRING => {
+                    let quote = error.span.unwrap();
+                    return Err(unterminated()
+                        .with_secondary(quote, "this quote opens a string inside the interpolation".to_string())
+                        .with_help("close the interpolation with '}' before the end of the string".to_string()));
+                }
+                Some(Err(error)) => return Err(error),
+                None => return Err(unterminated())
+            };
+            match token.t_type {
+                TokenType::RCurlyBracket if depth == 0 => break,
+                TokenType::RCurlyBracket => depth -= 1,
+                TokenType::LCurlyBracket => depth += 1,
+                _ => ()
+            }
+            tokens.push(token);
+        }
+        if tokens.is_empty() {
+            return Err(self.error(code::INVALID_INTERPOLATION, "Empty interpolation in string literal".to_string(), start)
+                .with_help("write '\\{' to insert a literal brace".to_string()));
+        }
+        Ok(tokens)
+    }
+
+    fn next_escape(&mut self) -> Result<char, Diagnostic> {
+        let start = self.position;
+        self.skip_char();
+        let escaped = match self.chars.peek() {
+            Some(&escaped) => escaped,
+            None => return Err(self.error(code::INVALID_ESCAPE, "Reached EOF in an escape sequence".to_string(), start))
+        };
+        self.skip_char();
+        match escaped {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '"' => Ok('"'),
+            '\\' => Ok('\\'),
+            '{' => Ok('{'),
+            '}' => Ok('}'),
+            'u' => self.next_unicode_escape(start),
+            unexpected => Err(self.error(code::INVALID_ESCAPE, format!("Unknown escape sequence '\\{}'", unexpected), start)
+                .with_help("the supported escapes are \\n, \\t, \\r, \\0, \\\", \\\\, \\{, \\} and \\u{...}".to_string()))
         }
     }
 
-    fn next_number(&mut self) -> Result<Token, String> {
+    //The '\\u' was already consumed, the code point is written in hexadecimal between braces
+    fn next_unicode_escape(&mut self, start: Position) -> Result<char, Diagnostic> {
+        if self.chars.peek() != Some(&'{') {
+            return Err(self.error(code::INVALID_ESCAPE, "Expected '{' after '\\u'".to_string(), start));
+        }
+        self.skip_char();
+        let mut digits = String::new();
+        while self.chars.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+            digits.push(self.skip_char());
+        }
+        if self.chars.peek() != Some(&'}') {
+            return Err(self.error(code::INVALID_ESCAPE, "Expected '}' closing the unicode escape".to_string(), start));
+        }
+        self.skip_char();
+        u32::from_str_radix(&digits, 16).ok()
+            .filter(|_| digits.len() <= 6)
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error(code::INVALID_ESCAPE,
+                                      format!("'{}' is not a valid unicode code point", digits), start))
+    }
+
+    fn next_number(&mut self, start: Position) -> Result<Token, Diagnostic> {
         let mut number = "".to_string();
-        while self.chars.peek().map_or(false, |c| c.is_numeric()) {
-            self.current_char += 1;
-            number.push(self.chars.next().unwrap());
-        }
-        if self.chars.peek().map_or(false, |c| *c == '.') {
-            self.chars.next();
-            let mut mantissa = ".".to_string();
-            while self.chars.peek().map_or(false, |c| c.is_numeric()) {
-                self.current_char += 1;
-                mantissa.push(self.chars.next().unwrap())
+        while self.chars.peek().is_some_and(char::is_ascii_digit) {
+            number.push(self.skip_char());
+        }
+        //The '..' of a range like `0..10` does not start a fraction
+        if self.chars.peek().is_some_and(|c| *c == '.') && self.chars.clone().nth(1) != Some('.') {
+            number.push(self.skip_char());
+            while self.chars.peek().is_some_and(char::is_ascii_digit) {
+                number.push(self.skip_char());
+            }
+            match number.parse() {
+                Ok(value) => self.construct_token(TokenType::FloatLiteral(value), start),
+                Err(_) => Err(self.error(code::LITERAL_OUT_OF_RANGE,
+                                         format!("Float literal {} is not a valid float", number), start))
             }
-            number.push_str(mantissa.as_ref());
-            self.construct_token(TokenType::FloatLiteral(number.parse().unwrap()))
         } else {
-            self.construct_token(TokenType::IntegerLiteral(number.parse().unwrap()))
+            match number.parse::<u32>() {
+                Ok(value) if value <= i32::MIN.unsigned_abs() => self.construct_token(TokenType::IntegerLiteral(value), start),
+                _ => Err(self.error(code::LITERAL_OUT_OF_RANGE,
+                                         format!("Integer literal {} does not fit in an int", number), start))
+            }
         }
     }
 
-    fn next_assignment(&mut self) -> Result<Token, String> {
+    fn next_dot(&mut self, start: Position) -> Result<Token, Diagnostic> {
         self.skip_char();
-        match self.chars.next() {
-            Some('=') => self.construct_token(TokenType::Assignment),
-            Some(unexpected) => Err(format!("Unexpected '{}' while expecting '=' on line {} char {}",
-                                            unexpected, self.current_line, self.current_char)),
-            None => Err(format!("Reached EOF while expecting '=' on line {} char {}",
-                                self.current_line, self.current_char))
+        if self.chars.peek() != Some(&'.') {
+            return self.construct_token(TokenType::Dot, start);
+        }
+        self.skip_char();
+        match self.chars.peek() {
+            Some('=') => self.skip_and_construct(TokenType::DotDotEq, start),
+            _ => self.construct_token(TokenType::DotDot, start)
         }
     }
 
-    fn construct_token(&self, t_type: TokenType) -> Result<Token, String> {
-        Ok(Token::new(t_type, self.current_line, self.current_char))
+    fn next_assignment(&mut self, start: Position) -> Result<Token, Diagnostic> {
+        self.skip_char();
+        match self.chars.peek() {
+            Some('=') => self.skip_and_construct(TokenType::Assignment, start),
+            Some(':') => self.skip_and_construct(TokenType::DoubleColon, start),
+            //A lone ':' ends the label of a loop or the name of a field
+            _ => self.construct_token(TokenType::Colon, start)
+        }
     }
 
-    fn skip_and_construct(&mut self, t_type: TokenType) -> Result<Token, String> {
+    //An arithmetic or bitwise operator directly followed by '=' assigns its result, like '+='
+    fn compound_assignment(&mut self, token: Token) -> Token {
+        match token.t_type {
+            TokenType::Operator(op, _, _) if self.chars.peek() == Some(&'=') &&
+                matches!(op, Operator::Add | Operator::Sub | Operator::Mul | Operator::Div | Operator::Rem |
+                             Operator::Pow | Operator::BitAnd | Operator::BitOr | Operator::BitXor |
+                             Operator::Shl | Operator::Shr) => {
+                self.skip_char();
+                Token::new(TokenType::CompoundAssignment(op), Span::new(token.span.start, self.position))
+            }
+            _ => token
+        }
+    }
+
+    //'<' and '>' alone, followed by '=' or doubled into a shift
+    fn next_comparison(&mut self, single: Operator, with_equals: Operator, shift: Operator,
+                       start: Position) -> Result<Token, Diagnostic> {
+        let current = self.skip_char();
+        match self.chars.peek() {
+            Some('=') => self.skip_and_construct(TokenType::Operator(with_equals, 2, false), start),
+            Some(&next) if next == current => self.skip_and_construct(TokenType::Operator(shift, 6, true), start),
+            _ => self.construct_token(TokenType::Operator(single, 2, false), start)
+        }
+    }
+
+    //A single '=' is the old spelling of '==', still accepted for the existing programs
+    fn next_equality(&mut self, start: Position) -> Result<Token, Diagnostic> {
+        self.skip_char();
+        match self.chars.peek() {
+            Some('=') => return self.skip_and_construct(TokenType::Operator(Operator::Eq, 2, false), start),
+            Some('>') => return self.skip_and_construct(TokenType::Arrow, start),
+            _ => ()
+        }
+        let span = Span::new(start, self.position);
+        self.warnings.push(Diagnostic::warning(Phase::Lexing, code::DEPRECATED_SYNTAX,
+                                               "Comparing with '=' is deprecated".to_string(), Some(span))
+            .with_label("replace with '=='".to_string())
+            .with_help("'=' will be removed in a future version, ':=' is the assignment".to_string()));
+        self.construct_token(TokenType::Operator(Operator::Eq, 2, false), start)
+    }
+
+    //`single` for a lone character, `doubled` when it is repeated like in '&&'
+    fn next_doubled(&mut self, single: TokenType, doubled: TokenType, start: Position) -> Result<Token, Diagnostic> {
+        let current = self.skip_char();
+        if self.chars.peek() == Some(&current) {
+            self.skip_and_construct(doubled, start)
+        } else {
+            self.construct_token(single, start)
+        }
+    }
+
+    fn construct_token(&self, t_type: TokenType, start: Position) -> Result<Token, Diagnostic> {
+        Ok(Token::new(t_type, Span::new(start, self.position)))
+    }
+
+    fn skip_and_construct(&mut self, t_type: TokenType, start: Position) -> Result<Token, Diagnostic> {
         self.skip_char();
-        self.construct_token(t_type)
+        self.construct_token(t_type, start)
+    }
+
+    fn error(&self, code: &'static str, message: String, start: Position) -> Diagnostic {
+        Diagnostic::error(Phase::Lexing, code, message, Some(Span::new(start, self.position)))
     }
 
-    fn skip_char(&mut self) {
+    fn skip_char(&mut self) -> char {
         match self.chars.next() {
             None => panic!("Lexer bad state, called skip_char but there are no chars left to consume"),
             Some(next_char) => {
+                self.position.offset += next_char.len_utf8();
                 match next_char {
                     '\n' => {
-                        self.current_char = 0;
-                        self.current_line += 1;
+                        self.position.column = 1;
+                        self.position.line += 1;
                     }
-                    _ => self.current_char += 1
+                    _ => self.position.column += 1
                 }
+                next_char
             }
         }
     }
@@ -1,15 +1,19 @@
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::iter::Peekable;
 use std::slice::Iter;
 
 use lazy_static::lazy_static;
 
-use crate::option_propagate_failure_to_result;
-use crate::parsing::ast::{AST, Expression, Type};
-use crate::parsing::token::{Token, TokenType};
+use crate::diagnostic::{code, Diagnostic, Phase, Position, Span};
+use crate::parsing::ast::{Accessor, AST, ASTKind, Expression, ExpressionKind, Iterable, MatchArm, Pattern, Type};
+use crate::parsing::token::{Operator, StringSegment, Token, TokenType, UnaryOperator};
 use crate::result_propagate_failure_to_result;
 use crate::macros::Stack;
 
+//Prefix operators bind tighter than the binary ones, except for the power: -2 ** 2 is -(2 ** 2)
+const UNARY_PRIORITY: u8 = 9;
+
 lazy_static! {
     static ref PREDEFINED_TYPES: HashMap<&'static str, Type> = {
         let mut m = HashMap::new();
@@ -17,229 +21,1028 @@ lazy_static! {
         m.insert("float",Type::FloatingPoint);
         m.insert("unit", Type::Unit);
         m.insert("bool", Type::Boolean);
+        m.insert("string", Type::String);
         m
     };
 }
 
 pub struct Parser<'a> {
-    tokens: Peekable<Iter<'a, Token>>
+    tokens: Peekable<Iter<'a, Token>>,
+    //Span of the last consumed token, used to close the span of the node being parsed
+    last_span: Span,
+    last_token: Option<&'a Token>,
+    eof: Span,
+    //Errors of the statements skipped while recovering
+    errors: Vec<Diagnostic>,
+    //Struct literals and match expressions being parsed, their '}' ends the expression of a field or an arm
+    open_braces: usize,
+    //The '>>' closing two nested map types was consumed by the inner one, the outer one is closed too
+    split_shift: bool,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(tokens: &'a Vec<Token>) -> Self {
+    pub fn new(tokens: &'a [Token]) -> Self {
+        let end = tokens.last().map_or(Position::start(), |token| token.span.end);
         Self {
-            tokens: tokens.iter().peekable()
+            tokens: tokens.iter().peekable(),
+            last_span: Span::new(end, end),
+            last_token: None,
+            eof: Span::new(end, end),
+            errors: Vec::new(),
+            open_braces: 0,
+            split_shift: false,
+        }
+    }
+
+    //Parses the whole program, reporting every error found instead of stopping at the first one
+    pub fn parse(&mut self) -> Result<AST, Vec<Diagnostic>> {
+        let start = self.peek_span();
+        let mut statements = Vec::new();
+        loop {
+            statements.append(&mut self.parse_statements());
+            match self.next() {
+                None => break,
+                //Unmatched '}', the statements after it are still parsed
+                Some(token) => {
+                    let error = self.unexpected("statement", Some(token));
+                    self.errors.push(error);
+                }
+            }
+        }
+        if !self.errors.is_empty() {
+            return Err(std::mem::take(&mut self.errors));
+        }
+        Ok(AST::new(ASTKind::Block(statements), start.to(self.last_span)))
+    }
+
+    //Parses input made of a single expression, optionally terminated by ';'
+    pub fn parse_single_expression(&mut self) -> Result<Expression, Diagnostic> {
+        let expr = result_propagate_failure_to_result!(self.parse_expression());
+        //The expression was the condition of a statement like `if x {`
+        if let Some(token) = self.last_token.filter(|token| token.t_type == TokenType::LCurlyBracket) {
+            return Err(self.unexpected("expression", Some(token)));
+        }
+        match self.peek() {
+            None => Ok(expr),
+            Some(token) => Err(self.unexpected("end of the expression", Some(token)))
         }
     }
-}
 
-impl Parser<'_> {
-    pub fn parse(&mut self) -> Result<AST, String> {
-        self.parse_block()
+    fn parse_block(&mut self) -> AST {
+        let start = self.peek_span();
+        let statements = self.parse_statements();
+        AST::new(ASTKind::Block(statements), start.to(self.last_span))
     }
 
-    fn parse_block(&mut self) -> Result<AST, String> {
+    //Parses statements until '}' or EOF, a statement that fails to parse is recorded and skipped
+    fn parse_statements(&mut self) -> Vec<AST> {
         let mut statements: Vec<AST> = Vec::new();
         while let Some(parsed_statement) = self.parse_statement() {
             match parsed_statement {
-                Err(msg) => return Err(msg),
-                Ok(parsed) => statements.push(parsed)
+                Ok(statement) => statements.push(statement),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.synchronize();
+                }
+            }
+        }
+        statements
+    }
+
+    //Skips the rest of a broken statement, up to and including its ';', or up to the '}' closing
+    //the enclosing block. Nested blocks are skipped as a whole, including one the error was found in.
+    fn synchronize(&mut self) {
+        let mut depth = match self.last_token.map(|token| &token.t_type) {
+            Some(TokenType::Semicolon) => return,
+            Some(TokenType::LCurlyBracket) => 1,
+            _ => 0
+        };
+        while let Some(token) = self.peek() {
+            match token.t_type {
+                TokenType::Semicolon if depth == 0 => {
+                    self.next();
+                    return;
+                }
+                TokenType::RCurlyBracket if depth == 0 => return,
+                //A statement ending with a block, like an if, is over once the block is closed
+                TokenType::RCurlyBracket if depth == 1 => {
+                    self.next();
+                    return;
+                }
+                TokenType::RCurlyBracket => depth -= 1,
+                TokenType::LCurlyBracket => depth += 1,
+                _ => ()
             }
+            self.next();
         }
-        Ok(AST::Block(statements))
     }
 
-    fn parse_statement(&mut self) -> Option<Result<AST, String>> {
-        match self.tokens.peek() {
+    fn parse_statement(&mut self) -> Option<Result<AST, Diagnostic>> {
+        match self.peek() {
             None => None,
             Some(token) => {
                 match &token.t_type {
                     TokenType::RCurlyBracket => None,
                     TokenType::Fn => Some(self.parse_function()),
+                    TokenType::Struct => Some(self.parse_struct()),
+                    TokenType::Enum => Some(self.parse_enum()),
+                    TokenType::Id(_) if self.tokens.clone().nth(1).is_some_and(|next| next.t_type == TokenType::Colon) =>
+                        Some(self.parse_labeled_loop()),
                     TokenType::Id(_) => Some(self.parse_assignment_or_declaration()),
                     TokenType::Print => Some(self.parse_print()),
                     TokenType::If => Some(self.parse_if()),
-                    TokenType::While => Some(self.parse_while()),
-                    TokenType::For => Some(self.parse_for()),
-                    _ => Some(Err(format!("Expected fn, identifier, if but found '{}' on line {} char {}",
-                                          token.t_type, token.line, token.char)))
+                    TokenType::Match => Some(self.parse_match()),
+                    TokenType::While => Some(self.parse_while(None)),
+                    TokenType::For => Some(self.parse_for(None)),
+                    TokenType::Break | TokenType::Continue => Some(self.parse_loop_control()),
+                    TokenType::Return => Some(self.parse_return()),
+                    _ => {
+                        self.next();
+                        Some(Err(self.unexpected("Fn, struct, enum, identifier, print, if, match, while, for, break, \
+                                                  continue or return", Some(token))))
+                    }
                 }
             }
         }
     }
 
-    fn parse_assignment_or_declaration(&mut self) -> Result<AST, String> {
-        let type_or_id = match self.tokens.next() {
-            Some(token) => match &token.t_type {
-                TokenType::Id(type_or_id) => type_or_id.to_string(),
-                _ => panic!("Parser bad state called expected identifier")
-            },
-            _ => panic!("Parser bad state called expected identifier")
-        };
-        match self.tokens.peek() {
-            None => Err(format!("Expected ':=' or 'identifier' but EOF reached")),
+    fn parse_assignment_or_declaration(&mut self) -> Result<AST, Diagnostic> {
+        let (type_or_id, start) = result_propagate_failure_to_result!(self.expect_identifier());
+        match self.peek() {
             Some(token) => match &token.t_type {
                 //Assignment
-                TokenType::Assignment => self.parse_assignment(type_or_id),
-                //Declaration
-                TokenType::Id(_) => self.parse_declaration(type_or_id),
-                unexpected => Err(format!("Expected ':=' or 'identifier' but {} found on line {} char {}",
-                                          unexpected, token.line, token.char))
-            }
+                TokenType::Assignment => self.parse_assignment(type_or_id, start),
+                TokenType::CompoundAssignment(op) => self.parse_compound_assignment(type_or_id, *op, start),
+                //Assignment to a member, or method call
+                TokenType::Dot => self.parse_member_assignment(type_or_id, start),
+                //Declaration, the type can be an array like `int[] xs`
+                TokenType::Id(_) => self.parse_declaration(type_or_id, start),
+                TokenType::LSquareBracket if self.at_array_type() => self.parse_declaration(type_or_id, start),
+                TokenType::Operator(Operator::Lt, _, _) if type_or_id == "map" => self.parse_declaration(type_or_id, start),
+                //Assignment to an element
+                TokenType::LSquareBracket => self.parse_member_assignment(type_or_id, start),
+                //Function call
+                TokenType::LRoundBracket => self.parse_call_statement(type_or_id, start),
+                _ => Err(self.unexpected("':=', '.', '[', '(' or identifier", Some(token)))
+            },
+            None => Err(self.unexpected("':=', '.', '[', '(' or identifier", None))
         }
     }
 
-    fn parse_print(&mut self) -> Result<AST, String> {
-        self.tokens.next();
+    fn parse_print(&mut self) -> Result<AST, Diagnostic> {
+        let start = self.peek_span();
+        self.next();
         let expr = result_propagate_failure_to_result!(self.parse_expression());
-        Ok(AST::Print(expr))
+        Ok(AST::new(ASTKind::Print(expr), start.to(self.last_span)))
     }
 
-    fn parse_assignment(&mut self, id: String) -> Result<AST, String> {
-        self.tokens.next();
+    fn parse_assignment(&mut self, id: String, start: Span) -> Result<AST, Diagnostic> {
+        self.next();
         let expr = result_propagate_failure_to_result!(self.parse_expression());
-        Ok(AST::Assign(id, expr))
+        Ok(AST::new(ASTKind::Assign(id, expr), start.to(self.last_span)))
     }
 
-    fn parse_declaration(&mut self, type_name: String) -> Result<AST, String> {
-        let (id, _, _) = match self.tokens.next() {
+    fn parse_compound_assignment(&mut self, id: String, op: Operator, start: Span) -> Result<AST, Diagnostic> {
+        self.next();
+        let expr = result_propagate_failure_to_result!(self.parse_expression());
+        Ok(AST::new(ASTKind::CompoundAssign(id, op, expr), start.to(self.last_span)))
+    }
+
+    fn parse_member_assignment(&mut self, id: String, start: Span) -> Result<AST, Diagnostic> {
+        let mut path = Vec::new();
+        //End of each step of the path, a method call needs the span of its target
+        let mut ends = Vec::new();
+        loop {
+            match self.peek() {
+                Some(token) if token.t_type == TokenType::Dot => {
+                    self.next();
+                    path.push(Accessor::Field(result_propagate_failure_to_result!(self.expect_identifier()).0));
+                }
+                Some(token) if token.t_type == TokenType::LSquareBracket => {
+                    self.next();
+                    let index = result_propagate_failure_to_result!(self.parse_expression());
+                    result_propagate_failure_to_result!(self.expect(TokenType::RSquareBracket));
+                    path.push(Accessor::Index(index));
+                }
+                _ => break
+            }
+            ends.push(self.last_span);
+        }
+        let op = match self.next() {
             Some(token) => match &token.t_type {
-                TokenType::Id(id) => (id, token.line, token.char),
-                _ => panic!("Parser bad state called expected identifier")
+                TokenType::Assignment => None,
+                TokenType::CompoundAssignment(op) => Some(*op),
+                TokenType::LRoundBracket if matches!(path.last(), Some(Accessor::Field(_))) =>
+                    return self.parse_method_statement(id, path, ends, start),
+                _ => return Err(self.unexpected("':=', '.', '[' or '('", Some(token)))
             },
-            _ => panic!("Parser bad state called expected identifier")
+            None => return Err(self.unexpected("':=', '.', '[' or '('", None))
         };
-        let found_type = match PREDEFINED_TYPES.get(type_name.as_str()) {
-            Some(found_type) => found_type.clone(),
-            None => Type::Custom(type_name)
+        let expr = result_propagate_failure_to_result!(self.parse_expression());
+        Ok(AST::new(ASTKind::MemberAssign(id, path, op, expr), start.to(self.last_span)))
+    }
+
+    //The last field of the path is the method, the rest is its target
+    fn parse_method_statement(&mut self, id: String, mut path: Vec<Accessor>, ends: Vec<Span>,
+                              start: Span) -> Result<AST, Diagnostic> {
+        let method = match path.pop() {
+            Some(Accessor::Field(method)) => method,
+            _ => unreachable!("A method call must end with the name of the method")
         };
+        let mut target = Expression::new(ExpressionKind::Variable(id), start);
+        for (accessor, end) in path.into_iter().zip(ends) {
+            let kind = match accessor {
+                Accessor::Field(field) => ExpressionKind::FieldAccess(Box::new(target), field),
+                Accessor::Index(index) => ExpressionKind::Index(Box::new(target), Box::new(index))
+            };
+            target = Expression::new(kind, start.to(end));
+        }
+        let arguments = result_propagate_failure_to_result!(self.parse_arguments());
+        let call = Expression::new(ExpressionKind::MethodCall(Box::new(target), method, arguments), start.to(self.last_span));
+        result_propagate_failure_to_result!(self.expect(TokenType::Semicolon));
+        Ok(AST::new(ASTKind::ExpressionStatement(call), start.to(self.last_span)))
+    }
+
+    fn parse_call_statement(&mut self, id: String, start: Span) -> Result<AST, Diagnostic> {
+        self.next();
+        let arguments = result_propagate_failure_to_result!(self.parse_arguments());
+        let call = Expression::new(ExpressionKind::FunctionCall(id, arguments), start.to(self.last_span));
+        result_propagate_failure_to_result!(self.expect(TokenType::Semicolon));
+        Ok(AST::new(ASTKind::ExpressionStatement(call), start.to(self.last_span)))
+    }
+
+    fn parse_return(&mut self) -> Result<AST, Diagnostic> {
+        let start = self.peek_span();
+        self.next();
+        if self.tokens.peek().is_some_and(|token| token.t_type == TokenType::Semicolon) {
+            self.next();
+            return Ok(AST::new(ASTKind::Return(None), start.to(self.last_span)));
+        }
+        let expr = result_propagate_failure_to_result!(self.parse_expression());
+        Ok(AST::new(ASTKind::Return(Some(expr)), start.to(self.last_span)))
+    }
+
+    fn parse_declaration(&mut self, type_name: String, start: Span) -> Result<AST, Diagnostic> {
+        let found_type = result_propagate_failure_to_result!(self.parse_type_suffix(type_name));
+        let (id, _) = result_propagate_failure_to_result!(self.expect_identifier());
         //Simple Declaration or Declaration + Assignment
-        match self.tokens.peek() {
-            None => Err(format!("Expected ':=' or ';' but EOF reached")),
+        match self.peek() {
             Some(token) => match &token.t_type {
                 //Assignment declaration
                 TokenType::Assignment => {
-                    self.tokens.next();
+                    self.next();
                     let expr = result_propagate_failure_to_result!(self.parse_expression());
-                    Ok(AST::VarDeclarationAndAssignment(found_type, id.to_string(), expr))
+                    Ok(AST::new(ASTKind::VarDeclarationAndAssignment(found_type, id, expr), start.to(self.last_span)))
                 }
                 //Simple declaration
-                _ => Ok(AST::VarDeclaration(found_type, id.to_string()))
-            }
+                TokenType::Semicolon => {
+                    self.next();
+                    Ok(AST::new(ASTKind::VarDeclaration(found_type, id), start.to(self.last_span)))
+                }
+                _ => Err(self.unexpected("':=' or ';'", Some(token)))
+            },
+            None => Err(self.unexpected("':=' or ';'", None))
         }
     }
 
-    fn parse_expression(&mut self) -> Result<Expression, String> {
+    fn parse_expression(&mut self) -> Result<Expression, Diagnostic> {
+        let start = self.peek_span();
         let mut output: Vec<Expression> = Vec::new();
         let mut operators: Vec<Token> = Vec::new();
-        while let Some(token) = self.tokens.next() {
+        while let Some(&token) = self.tokens.peek() {
+            match &token.t_type {
+                //Argument separator or closing bracket of a call, left to parse_arguments
+                TokenType::Comma => break,
+                //Separator of a key and its value in a map literal
+                TokenType::Colon => break,
+                //Rest of the range of a for loop
+                TokenType::DotDot | TokenType::DotDotEq | TokenType::Step => break,
+                TokenType::RRoundBracket if !operators.iter().any(|op| op.t_type == TokenType::LRoundBracket) => break,
+                //Closing bracket of an index, left to the enclosing expression
+                TokenType::RSquareBracket => break,
+                //End of a struct literal or a match expression, left to the function parsing it
+                TokenType::RCurlyBracket if self.open_braces > 0 => break,
+                _ => ()
+            }
+            let after_operand = !output.is_empty() && self.last_token.is_some_and(|last| ends_operand(&last.t_type));
+            self.next();
             match &token.t_type {
                 TokenType::Semicolon | TokenType::LCurlyBracket => break,
-                TokenType::Id(id) => output.push(Expression::Variable(id.to_string())),
-                TokenType::BooleanLiteral(val) => output.push(Expression::BooleanLiteral(*val)),
-                TokenType::IntegerLiteral(val) => output.push(Expression::IntegerLiteral(*val)),
-                TokenType::FloatLiteral(val) => output.push(Expression::FloatLiteral(*val)),
+                TokenType::Id(id) => {
+                    if self.tokens.peek().is_some_and(|next| next.t_type == TokenType::LRoundBracket) {
+                        self.next();
+                        let arguments = result_propagate_failure_to_result!(self.parse_arguments());
+                        output.push(Expression::new(ExpressionKind::FunctionCall(id.to_string(), arguments),
+                                                    token.span.to(self.last_span)))
+                    } else if self.tokens.peek().is_some_and(|next| next.t_type == TokenType::DoubleColon) {
+                        let (variant, payload) = result_propagate_failure_to_result!(self.parse_enum_variant());
+                        output.push(Expression::new(ExpressionKind::EnumVariant(id.to_string(), variant, payload),
+                                                    token.span.to(self.last_span)))
+                    } else if self.at_struct_literal() {
+                        let fields = result_propagate_failure_to_result!(self.parse_struct_literal());
+                        output.push(Expression::new(ExpressionKind::StructLiteral(id.to_string(), fields),
+                                                    token.span.to(self.last_span)))
+                    } else {
+                        output.push(Expression::new(ExpressionKind::Variable(id.to_string()), token.span))
+                    }
+                }
+                TokenType::Match => {
+                    let value = result_propagate_failure_to_result!(self.parse_match_value());
+                    let arms = result_propagate_failure_to_result!(self.parse_match_expression_arms());
+                    output.push(Expression::new(ExpressionKind::Match(Box::new(value), arms), token.span.to(self.last_span)))
+                }
+                TokenType::BooleanLiteral(val) => output.push(Expression::new(ExpressionKind::BooleanLiteral(*val), token.span)),
+                TokenType::IntegerLiteral(val) => match i32::try_from(*val) {
+                    Ok(val) => output.push(Expression::new(ExpressionKind::IntegerLiteral(val), token.span)),
+                    //The smallest int is written as the negation of a literal that does not fit in an int, any other
+                    //use of that literal is reported like the ones the lexer rejects
+                    Err(_) => match operators.last() {
+                        Some(neg) if neg.t_type == TokenType::UnaryOperator(UnaryOperator::Neg) => {
+                            let span = neg.span.to(token.span);
+                            operators.pop();
+                            output.push(Expression::new(ExpressionKind::IntegerLiteral(i32::MIN), span))
+                        }
+                        _ => return Err(Diagnostic::error(Phase::Lexing, code::LITERAL_OUT_OF_RANGE,
+                                                          format!("Integer literal {} does not fit in an int", val),
+                                                          Some(token.span)))
+                    }
+                },
+                TokenType::FloatLiteral(val) => output.push(Expression::new(ExpressionKind::FloatLiteral(*val), token.span)),
+                TokenType::StringLiteral(val) => output.push(Expression::new(ExpressionKind::StringLiteral(val.clone()), token.span)),
+                TokenType::InterpolatedString(segments) =>
+                    output.push(result_propagate_failure_to_result!(parse_interpolation(segments, token.span))),
+                //Indexing binds tighter than any operator, it applies to the operand just parsed
+                TokenType::LSquareBracket if after_operand => {
+                    let target = output.pop().unwrap();
+                    let index = result_propagate_failure_to_result!(self.parse_expression());
+                    let end = result_propagate_failure_to_result!(self.expect(TokenType::RSquareBracket));
+                    let span = target.span.to(end);
+                    output.push(Expression::new(ExpressionKind::Index(Box::new(target), Box::new(index)), span))
+                }
+                TokenType::LSquareBracket => {
+                    let literal = result_propagate_failure_to_result!(self.parse_collection());
+                    output.push(Expression::new(literal, token.span.to(self.last_span)))
+                }
+                TokenType::Dot if after_operand => {
+                    let target = output.pop().unwrap();
+                    let (field, end) = result_propagate_failure_to_result!(self.expect_identifier());
+                    if self.tokens.peek().is_some_and(|next| next.t_type == TokenType::LRoundBracket) {
+                        self.next();
+                        let arguments = result_propagate_failure_to_result!(self.parse_arguments());
+                        let span = target.span.to(self.last_span);
+                        output.push(Expression::new(ExpressionKind::MethodCall(Box::new(target), field, arguments), span))
+                    } else {
+                        let span = target.span.to(end);
+                        output.push(Expression::new(ExpressionKind::FieldAccess(Box::new(target), field), span))
+                    }
+                }
                 TokenType::LRoundBracket => operators.push(token.clone()),
                 TokenType::RRoundBracket => {
-                    if !tilt_until(&mut operators, &mut output, TokenType::LRoundBracket) {
-                        return Err(format!("Expected ')' but found '{}' on line {} char {}",
-                                           token.t_type, token.line, token.char));
+                    if !result_propagate_failure_to_result!(tilt_until(&mut operators, &mut output, TokenType::LRoundBracket)) {
+                        return Err(self.unexpected("'('", Some(token)));
                     }
                 }
+                //Prefix operators apply to the operand that follows, nothing before them is reduced
+                TokenType::Operator(Operator::Sub, _, _) if !after_operand =>
+                    operators.push(Token::new(TokenType::UnaryOperator(UnaryOperator::Neg), token.span)),
+                TokenType::UnaryOperator(_) => operators.push(token.clone()),
                 TokenType::Operator(_, priority, left_ass) => {
                     while let Some(top) = operators.top() {
-                        match top.t_type {
-                            TokenType::LRoundBracket => break,
-                            TokenType::Operator(op, o_priority, _) => {
-                                if o_priority > *priority || (o_priority == *priority && *left_ass) {
-                                    operators.pop();
-                                    let right = output.pop().unwrap();
-                                    let left = output.pop().unwrap();
-                                    output.push(Expression::BinaryOperation(Box::new(left), op, Box::new(right)))
-                                } else {
-                                    break;
-                                }
-                            }
+                        let reduces = match top.t_type {
+                            TokenType::LRoundBracket => false,
+                            TokenType::Operator(_, o_priority, _) =>
+                                o_priority > *priority || (o_priority == *priority && *left_ass),
+                            TokenType::UnaryOperator(_) => UNARY_PRIORITY > *priority,
                             _ => unreachable!("This token must not be on the operator stack!")
+                        };
+                        if !reduces {
+                            break;
                         }
+                        operators.pop();
+                        result_propagate_failure_to_result!(reduce(&mut output, &top));
                     }
                     operators.push(token.clone());
                 }
-                _ => return Err(format!("Expression: Unexpected '{}' found on line {} char {}",
-                                        token.t_type, token.line, token.char))
+                _ => return Err(self.unexpected("expression", Some(token)))
             }
         }
         while let Some(token) = operators.pop() {
             match token.t_type {
-                TokenType::LRoundBracket => break,
-                TokenType::Operator(op, _, _) => {
-                    let right = output.pop().unwrap();
-                    let left = output.pop().unwrap();
-                    output.push(Expression::BinaryOperation(Box::new(left), op, Box::new(right)))
-                }
+                TokenType::LRoundBracket =>
+                    return Err(Diagnostic::error(Phase::Parsing, code::UNCLOSED_PARENTHESIS,
+                                                 "Unclosed '(' in expression".to_string(), Some(token.span))
+                        .with_label("'(' opened here is never closed".to_string())),
+                TokenType::Operator(_, _, _) | TokenType::UnaryOperator(_) =>
+                    result_propagate_failure_to_result!(reduce(&mut output, &token)),
                 _ => unreachable!("This token must not be on the operator stack!")
             }
         }
-        assert!(operators.is_empty()); //Ensure that the operator stack is empty as it should be.
+        if output.len() != 1 {
+            return Err(Diagnostic::error(Phase::Parsing, code::MALFORMED_EXPRESSION,
+                                         "Malformed expression, operands are not separated by operators".to_string(),
+                                         Some(start.to(self.last_span))));
+        }
         Ok(output.pop().unwrap())
     }
 
-    fn parse_if(&mut self) -> Result<AST, String> {
-        self.tokens.next();
+    //`Name {` starts a struct literal only when followed by `field:`, otherwise the brace opens the
+    //block of a statement like `if flag {`. A labeled loop at the start of that block is told apart
+    //by the keyword after its ':'.
+    fn at_struct_literal(&self) -> bool {
+        let mut tokens = self.tokens.clone();
+        match (tokens.next(), tokens.next(), tokens.next(), tokens.next()) {
+            (Some(open), Some(field), Some(colon), next) =>
+                open.t_type == TokenType::LCurlyBracket && matches!(field.t_type, TokenType::Id(_)) &&
+                    colon.t_type == TokenType::Colon &&
+                    !next.is_some_and(|next| matches!(next.t_type, TokenType::While | TokenType::For)),
+            _ => false
+        }
+    }
+
+    fn parse_struct_literal(&mut self) -> Result<Vec<(String, Expression)>, Diagnostic> {
+        self.next();
+        self.open_braces += 1;
+        let fields = self.parse_literal_fields();
+        self.open_braces -= 1;
+        fields
+    }
+
+    fn parse_literal_fields(&mut self) -> Result<Vec<(String, Expression)>, Diagnostic> {
+        let mut fields = Vec::new();
+        loop {
+            let (field, _) = result_propagate_failure_to_result!(self.expect_identifier());
+            result_propagate_failure_to_result!(self.expect(TokenType::Colon));
+            fields.push((field, result_propagate_failure_to_result!(self.parse_expression())));
+            if result_propagate_failure_to_result!(self.end_of_braced_list()) {
+                return Ok(fields);
+            }
+        }
+    }
+
+    //Parses `::Variant` or `::Variant(values)` after the name of an enum
+    fn parse_enum_variant(&mut self) -> Result<(String, Vec<Expression>), Diagnostic> {
+        self.next();
+        let (variant, _) = result_propagate_failure_to_result!(self.expect_identifier());
+        if !self.tokens.peek().is_some_and(|token| token.t_type == TokenType::LRoundBracket) {
+            return Ok((variant, Vec::new()));
+        }
+        self.next();
+        let payload = result_propagate_failure_to_result!(self.parse_arguments());
+        Ok((variant, payload))
+    }
+
+    //Parses the matched value, up to and including the '{' opening the arms
+    fn parse_match_value(&mut self) -> Result<Expression, Diagnostic> {
+        let value = result_propagate_failure_to_result!(self.parse_expression());
+        match self.last_token {
+            Some(token) if token.t_type == TokenType::LCurlyBracket => Ok(value),
+            token => Err(self.unexpected("'{' opening the arms of the match", token))
+        }
+    }
+
+    fn parse_match_expression_arms(&mut self) -> Result<Vec<MatchArm<Expression>>, Diagnostic> {
+        if self.tokens.peek().is_some_and(|token| token.t_type == TokenType::RCurlyBracket) {
+            self.next();
+            return Ok(Vec::new());
+        }
+        self.open_braces += 1;
+        let arms = self.parse_expression_arms();
+        self.open_braces -= 1;
+        arms
+    }
+
+    fn parse_expression_arms(&mut self) -> Result<Vec<MatchArm<Expression>>, Diagnostic> {
+        let mut arms = Vec::new();
+        loop {
+            let (pattern, span) = result_propagate_failure_to_result!(self.parse_pattern());
+            let body = result_propagate_failure_to_result!(self.parse_expression());
+            arms.push(MatchArm { pattern, span, body });
+            if result_propagate_failure_to_result!(self.end_of_braced_list()) {
+                return Ok(arms);
+            }
+        }
+    }
+
+    //Parses a pattern and the '=>' after it. A variant binds each value of its payload to a name or ignores it with `_`.
+    fn parse_pattern(&mut self) -> Result<(Pattern, Span), Diagnostic> {
+        let (id, start) = result_propagate_failure_to_result!(self.expect_identifier());
+        let pattern = if self.tokens.peek().is_some_and(|token| token.t_type == TokenType::DoubleColon) {
+            self.next();
+            let (variant, _) = result_propagate_failure_to_result!(self.expect_identifier());
+            let mut bindings = Vec::new();
+            if self.tokens.peek().is_some_and(|token| token.t_type == TokenType::LRoundBracket) {
+                self.next();
+                bindings = result_propagate_failure_to_result!(self.parse_bindings());
+            }
+            Pattern::Variant(id, variant, bindings)
+        } else {
+            Pattern::CatchAll(Some(id).filter(|id| id != "_"))
+        };
+        let span = start.to(self.last_span);
+        result_propagate_failure_to_result!(self.expect(TokenType::Arrow));
+        Ok((pattern, span))
+    }
+
+    //Names listed after a '(' up to the closing ')', None for a `_`
+    fn parse_bindings(&mut self) -> Result<Vec<Option<String>>, Diagnostic> {
+        let mut bindings = Vec::new();
+        loop {
+            let (binding, _) = result_propagate_failure_to_result!(self.expect_identifier());
+            bindings.push(Some(binding).filter(|binding| binding != "_"));
+            match self.next() {
+                Some(token) => match &token.t_type {
+                    TokenType::Comma => continue,
+                    TokenType::RRoundBracket => return Ok(bindings),
+                    _ => return Err(self.unexpected("',' or ')'", Some(token)))
+                },
+                None => return Err(self.unexpected("',' or ')'", None))
+            }
+        }
+    }
+
+    //Consumes the ',' or '}' after an item of a list between braces, true once the list is closed.
+    //A trailing comma is allowed.
+    fn end_of_braced_list(&mut self) -> Result<bool, Diagnostic> {
+        match self.next() {
+            Some(token) => match &token.t_type {
+                TokenType::Comma if self.tokens.peek().is_some_and(|next| next.t_type == TokenType::RCurlyBracket) => {
+                    self.next();
+                    Ok(true)
+                }
+                TokenType::Comma => Ok(false),
+                TokenType::RCurlyBracket => Ok(true),
+                _ => Err(self.unexpected("',' or '}'", Some(token)))
+            },
+            None => Err(self.unexpected("',' or '}'", None))
+        }
+    }
+
+    //Parses an array literal like `[1, 2]` or a map literal like `["a": 1]` after its '[', `[:]` is the empty map.
+    //A trailing comma is allowed.
+    fn parse_collection(&mut self) -> Result<ExpressionKind, Diagnostic> {
+        let mut tokens = self.tokens.clone();
+        if tokens.next().is_some_and(|token| token.t_type == TokenType::Colon) &&
+            tokens.next().is_some_and(|token| token.t_type == TokenType::RSquareBracket) {
+            self.next();
+            self.next();
+            return Ok(ExpressionKind::MapLiteral(Vec::new()));
+        }
+        let mut elements = Vec::new();
+        let mut entries = Vec::new();
+        //The first element tells whether the literal is a map
+        let mut is_map = None;
+        loop {
+            if self.tokens.peek().is_some_and(|token| token.t_type == TokenType::RSquareBracket) {
+                self.next();
+                break;
+            }
+            let element = result_propagate_failure_to_result!(self.parse_expression());
+            if *is_map.get_or_insert_with(|| self.tokens.peek().is_some_and(|token| token.t_type == TokenType::Colon)) {
+                result_propagate_failure_to_result!(self.expect(TokenType::Colon));
+                entries.push((element, result_propagate_failure_to_result!(self.parse_expression())));
+            } else {
+                elements.push(element);
+            }
+            match self.next() {
+                Some(token) => match &token.t_type {
+                    TokenType::Comma => continue,
+                    TokenType::RSquareBracket => break,
+                    _ => return Err(self.unexpected("',' or ']'", Some(token)))
+                },
+                None => return Err(self.unexpected("',' or ']'", None))
+            }
+        }
+        Ok(if is_map == Some(true) { ExpressionKind::MapLiteral(entries) } else { ExpressionKind::ArrayLiteral(elements) })
+    }
+
+    fn parse_arguments(&mut self) -> Result<Vec<Expression>, Diagnostic> {
+        let mut arguments = Vec::new();
+        if self.tokens.peek().is_some_and(|token| token.t_type == TokenType::RRoundBracket) {
+            self.next();
+            return Ok(arguments);
+        }
+        loop {
+            arguments.push(result_propagate_failure_to_result!(self.parse_expression()));
+            match self.next() {
+                Some(token) => match &token.t_type {
+                    TokenType::Comma => continue,
+                    TokenType::RRoundBracket => return Ok(arguments),
+                    _ => return Err(self.unexpected("',' or ')'", Some(token)))
+                },
+                None => return Err(self.unexpected("',' or ')'", None))
+            }
+        }
+    }
+
+    fn parse_if(&mut self) -> Result<AST, Diagnostic> {
+        let start = self.peek_span();
+        self.next();
         let clause = result_propagate_failure_to_result!(self.parse_expression());
-        let block = result_propagate_failure_to_result!(self.parse_block());
-        option_propagate_failure_to_result!(self.expect(TokenType::RCurlyBracket));
-        Ok(AST::IfStatement(clause, Box::new(block)))
+        let span = start.to(clause.span);
+        let block = self.parse_block();
+        result_propagate_failure_to_result!(self.expect(TokenType::RCurlyBracket));
+        let otherwise = match self.peek() {
+            Some(token) if token.t_type == TokenType::Else => {
+                self.next();
+                Some(Box::new(result_propagate_failure_to_result!(self.parse_else())))
+            }
+            _ => None
+        };
+        Ok(AST::new(ASTKind::IfStatement(clause, Box::new(block), otherwise), span))
+    }
+
+    fn parse_else(&mut self) -> Result<AST, Diagnostic> {
+        match self.peek() {
+            Some(token) if token.t_type == TokenType::If => self.parse_if(),
+            _ => {
+                result_propagate_failure_to_result!(self.expect(TokenType::LCurlyBracket));
+                let block = self.parse_block();
+                result_propagate_failure_to_result!(self.expect(TokenType::RCurlyBracket));
+                Ok(block)
+            }
+        }
+    }
+
+    //The arms of a match statement are blocks, they can be separated by commas
+    fn parse_match(&mut self) -> Result<AST, Diagnostic> {
+        let start = self.peek_span();
+        self.next();
+        let value = result_propagate_failure_to_result!(self.parse_match_value());
+        let span = start.to(value.span);
+        let mut arms = Vec::new();
+        while !self.tokens.peek().is_some_and(|token| token.t_type == TokenType::RCurlyBracket) {
+            let (pattern, pattern_span) = result_propagate_failure_to_result!(self.parse_pattern());
+            result_propagate_failure_to_result!(self.expect(TokenType::LCurlyBracket));
+            let body = self.parse_block();
+            result_propagate_failure_to_result!(self.expect(TokenType::RCurlyBracket));
+            arms.push(MatchArm { pattern, span: pattern_span, body });
+            if self.tokens.peek().is_some_and(|token| token.t_type == TokenType::Comma) {
+                self.next();
+            }
+        }
+        self.next();
+        Ok(AST::new(ASTKind::Match(value, arms), span))
+    }
+
+    //Parses `label: while ...` or `label: for ...`, the span of the loop includes its label
+    fn parse_labeled_loop(&mut self) -> Result<AST, Diagnostic> {
+        let (label, start) = result_propagate_failure_to_result!(self.expect_identifier());
+        self.next();
+        let mut ast = match self.peek() {
+            Some(token) if token.t_type == TokenType::While => result_propagate_failure_to_result!(self.parse_while(Some(label))),
+            Some(token) if token.t_type == TokenType::For => result_propagate_failure_to_result!(self.parse_for(Some(label))),
+            token => {
+                self.next();
+                return Err(self.unexpected("'while' or 'for' after a label", token));
+            }
+        };
+        ast.span = start.to(ast.span);
+        Ok(ast)
     }
 
-    fn parse_while(&mut self) -> Result<AST, String> {
-        self.tokens.next();
+    fn parse_while(&mut self, label: Option<String>) -> Result<AST, Diagnostic> {
+        let start = self.peek_span();
+        self.next();
         let clause = result_propagate_failure_to_result!(self.parse_expression());
-        let block = result_propagate_failure_to_result!(self.parse_block());
-        option_propagate_failure_to_result!(self.expect(TokenType::RCurlyBracket));
-        Ok(AST::WhileStatement(clause, Box::new(block)))
+        let span = start.to(clause.span);
+        let block = self.parse_block();
+        result_propagate_failure_to_result!(self.expect(TokenType::RCurlyBracket));
+        Ok(AST::new(ASTKind::WhileStatement(label, clause, Box::new(block)), span))
     }
 
-    fn parse_for(&mut self) -> Result<AST, String> {
-        self.tokens.next();
+    fn parse_for(&mut self, label: Option<String>) -> Result<AST, Diagnostic> {
+        let start = self.peek_span();
+        self.next();
+        let mut tokens = self.tokens.clone();
+        match (tokens.next(), tokens.next()) {
+            (Some(first), _) if first.t_type == TokenType::LRoundBracket => return self.parse_for_in(label, start),
+            (Some(first), Some(second)) if matches!(first.t_type, TokenType::Id(_)) && second.t_type == TokenType::In =>
+                return self.parse_for_in(label, start),
+            _ => ()
+        }
         let init = result_propagate_failure_to_result!(self.parse_assignment_or_declaration());
         let clause = result_propagate_failure_to_result!(self.parse_expression());
         let inc = result_propagate_failure_to_result!(self.parse_assignment_or_declaration());
-        let block = result_propagate_failure_to_result!(self.parse_block());
-        option_propagate_failure_to_result!(self.expect(TokenType::RCurlyBracket));
-        Ok(AST::ForStatement(Box::new(init), clause, Box::new(inc), Box::new(block)))
+        let span = start.to(inc.span);
+        let block = self.parse_block();
+        result_propagate_failure_to_result!(self.expect(TokenType::RCurlyBracket));
+        Ok(AST::new(ASTKind::ForStatement(label, Box::new(init), clause, Box::new(inc), Box::new(block)), span))
+    }
+
+    //Parses `for i in 0..10 step 2 {`, `for x in xs {` or `for (k, v) in m {` after the 'for'
+    fn parse_for_in(&mut self, label: Option<String>, start: Span) -> Result<AST, Diagnostic> {
+        let bindings = if self.tokens.peek().is_some_and(|token| token.t_type == TokenType::LRoundBracket) {
+            self.next();
+            result_propagate_failure_to_result!(self.parse_bindings())
+        } else {
+            let (binding, _) = result_propagate_failure_to_result!(self.expect_identifier());
+            vec![Some(binding).filter(|binding| binding != "_")]
+        };
+        result_propagate_failure_to_result!(self.expect(TokenType::In));
+        let first = result_propagate_failure_to_result!(self.parse_expression());
+        let iterable = match self.peek() {
+            Some(token) if matches!(token.t_type, TokenType::DotDot | TokenType::DotDotEq) => {
+                self.next();
+                let end = result_propagate_failure_to_result!(self.parse_expression());
+                let step = if self.tokens.peek().is_some_and(|token| token.t_type == TokenType::Step) {
+                    self.next();
+                    Some(Box::new(result_propagate_failure_to_result!(self.parse_expression())))
+                } else {
+                    None
+                };
+                Iterable::Range(Box::new(first), Box::new(end), token.t_type == TokenType::DotDotEq, step)
+            }
+            _ => Iterable::Collection(first)
+        };
+        let span = start.to(self.last_span);
+        let block = self.parse_block();
+        result_propagate_failure_to_result!(self.expect(TokenType::RCurlyBracket));
+        Ok(AST::new(ASTKind::ForInStatement(label, bindings, iterable, Box::new(block)), span))
     }
 
-    fn parse_function(&mut self) -> Result<AST, String> {
-        unimplemented!()
+    fn parse_loop_control(&mut self) -> Result<AST, Diagnostic> {
+        let start = self.peek_span();
+        let is_break = self.next().is_some_and(|token| token.t_type == TokenType::Break);
+        let label = match self.peek() {
+            Some(token) if token.t_type != TokenType::Semicolon =>
+                Some(result_propagate_failure_to_result!(self.expect_identifier()).0),
+            _ => None
+        };
+        result_propagate_failure_to_result!(self.expect(TokenType::Semicolon));
+        let kind = if is_break { ASTKind::Break(label) } else { ASTKind::Continue(label) };
+        Ok(AST::new(kind, start.to(self.last_span)))
+    }
+
+    fn parse_struct(&mut self) -> Result<AST, Diagnostic> {
+        let start = self.peek_span();
+        self.next();
+        let (id, _) = result_propagate_failure_to_result!(self.expect_identifier());
+        let span = start.to(self.last_span);
+        result_propagate_failure_to_result!(self.expect(TokenType::LCurlyBracket));
+        //The literal of a struct without fields would read as a block, `S {}`, so it could never be built
+        if self.tokens.peek().is_some_and(|token| token.t_type == TokenType::RCurlyBracket) {
+            return Err(Diagnostic::error(Phase::Parsing, code::EMPTY_STRUCT,
+                                         format!("Struct {} must declare at least one field", id), Some(span))
+                .with_label("declared without fields".to_string())
+                .with_help(format!("add a field, like `struct {} {{ int field }}`", id)));
+        }
+        let mut fields = Vec::new();
+        loop {
+            let field_type = result_propagate_failure_to_result!(self.parse_type());
+            let (field, _) = result_propagate_failure_to_result!(self.expect_identifier());
+            fields.push((field_type, field));
+            if result_propagate_failure_to_result!(self.end_of_braced_list()) {
+                break;
+            }
+        }
+        Ok(AST::new(ASTKind::StructDeclaration(id, fields), span))
     }
 
-    fn expect(&mut self, expected: TokenType) -> Option<Result<AST, String>> {
-        match self.tokens.next() {
-            Some(token) => if token.t_type == expected { None } else {
-                Some(Err(format!("Expected '{}' but '{}' found on line {} char {}",
-                                 expected, token.t_type, token.line, token.char)))
+    fn parse_enum(&mut self) -> Result<AST, Diagnostic> {
+        let start = self.peek_span();
+        self.next();
+        let (id, _) = result_propagate_failure_to_result!(self.expect_identifier());
+        let span = start.to(self.last_span);
+        result_propagate_failure_to_result!(self.expect(TokenType::LCurlyBracket));
+        let mut variants = Vec::new();
+        //An enum without variants has no values, a match on it needs no arm
+        if self.tokens.peek().is_some_and(|token| token.t_type == TokenType::RCurlyBracket) {
+            self.next();
+        } else {
+            loop {
+                variants.push(result_propagate_failure_to_result!(self.parse_variant()));
+                if result_propagate_failure_to_result!(self.end_of_braced_list()) {
+                    break;
+                }
+            }
+        }
+        Ok(AST::new(ASTKind::EnumDeclaration(id, variants), span))
+    }
+
+    //Parses a variant of an enum declaration with the types of its payload, like `Rect(int, int)`
+    fn parse_variant(&mut self) -> Result<(String, Vec<Type>), Diagnostic> {
+        let (variant, _) = result_propagate_failure_to_result!(self.expect_identifier());
+        let mut payload = Vec::new();
+        if self.tokens.peek().is_some_and(|token| token.t_type == TokenType::LRoundBracket) {
+            self.next();
+            loop {
+                payload.push(result_propagate_failure_to_result!(self.parse_type()));
+                match self.next() {
+                    Some(token) => match &token.t_type {
+                        TokenType::Comma => continue,
+                        TokenType::RRoundBracket => break,
+                        _ => return Err(self.unexpected("',' or ')'", Some(token)))
+                    },
+                    None => return Err(self.unexpected("',' or ')'", None))
+                }
+            }
+        }
+        Ok((variant, payload))
+    }
+
+    fn parse_function(&mut self) -> Result<AST, Diagnostic> {
+        let start = self.peek_span();
+        self.next();
+        let (id, _) = result_propagate_failure_to_result!(self.expect_identifier());
+        result_propagate_failure_to_result!(self.expect(TokenType::LRoundBracket));
+        let mut parameters = Vec::new();
+        if self.tokens.peek().is_some_and(|token| token.t_type == TokenType::RRoundBracket) {
+            self.next();
+        } else {
+            loop {
+                let parameter_type = result_propagate_failure_to_result!(self.parse_type());
+                let (parameter, _) = result_propagate_failure_to_result!(self.expect_identifier());
+                parameters.push((parameter_type, parameter));
+                match self.next() {
+                    Some(token) => match &token.t_type {
+                        TokenType::Comma => continue,
+                        TokenType::RRoundBracket => break,
+                        _ => return Err(self.unexpected("',' or ')'", Some(token)))
+                    },
+                    None => return Err(self.unexpected("',' or ')'", None))
+                }
+            }
+        }
+        //The return type can be omitted for functions returning unit
+        let return_type = match self.peek() {
+            Some(token) if token.t_type == TokenType::LCurlyBracket => Type::Unit,
+            _ => result_propagate_failure_to_result!(self.parse_type())
+        };
+        let span = start.to(self.last_span);
+        result_propagate_failure_to_result!(self.expect(TokenType::LCurlyBracket));
+        let body = self.parse_block();
+        result_propagate_failure_to_result!(self.expect(TokenType::RCurlyBracket));
+        Ok(AST::new(ASTKind::FunctionDeclaration(id, parameters, return_type, Box::new(body)), span))
+    }
+
+    fn parse_type(&mut self) -> Result<Type, Diagnostic> {
+        let (type_name, _) = result_propagate_failure_to_result!(self.expect_identifier());
+        self.parse_type_suffix(type_name)
+    }
+
+    //Parses what follows the name of a type: the parameters of a map, then each '[]' making an array of it
+    fn parse_type_suffix(&mut self, type_name: String) -> Result<Type, Diagnostic> {
+        let mut found_type = match self.peek() {
+            Some(token) if type_name == "map" && matches!(token.t_type, TokenType::Operator(Operator::Lt, _, _)) =>
+                result_propagate_failure_to_result!(self.parse_map_type()),
+            _ => resolve_type(type_name)
+        };
+        //The tokens after a split '>>' belong to the enclosing map
+        while !self.split_shift && self.at_array_type() {
+            self.next();
+            self.next();
+            found_type = Type::Array(Box::new(found_type));
+        }
+        Ok(found_type)
+    }
+
+    //Parses `<K, V>` after `map`
+    fn parse_map_type(&mut self) -> Result<Type, Diagnostic> {
+        self.next();
+        let key = result_propagate_failure_to_result!(self.parse_type());
+        result_propagate_failure_to_result!(self.expect(TokenType::Comma));
+        let value = result_propagate_failure_to_result!(self.parse_type());
+        if self.split_shift {
+            self.split_shift = false;
+        } else {
+            match self.next() {
+                Some(token) => match &token.t_type {
+                    TokenType::Operator(Operator::Gt, _, _) => (),
+                    //Closes a nested map too, like in `map<int, map<int, int>>`
+                    TokenType::Operator(Operator::Shr, _, _) => self.split_shift = true,
+                    _ => return Err(self.unexpected("'>'", Some(token)))
+                },
+                None => return Err(self.unexpected("'>'", None))
+            }
+        }
+        Ok(Type::Map(Box::new(key), Box::new(value)))
+    }
+
+    fn at_array_type(&self) -> bool {
+        let mut tokens = self.tokens.clone();
+        tokens.next().is_some_and(|token| token.t_type == TokenType::LSquareBracket) &&
+            tokens.next().is_some_and(|token| token.t_type == TokenType::RSquareBracket)
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.next()?;
+        self.last_span = token.span;
+        self.last_token = Some(token);
+        Some(token)
+    }
+
+    fn peek(&mut self) -> Option<&'a Token> {
+        self.tokens.peek().copied()
+    }
+
+    fn peek_span(&mut self) -> Span {
+        self.tokens.peek().map_or(self.eof, |token| token.span)
+    }
+
+    fn expect_identifier(&mut self) -> Result<(String, Span), Diagnostic> {
+        match self.next() {
+            Some(token) => match &token.t_type {
+                TokenType::Id(id) => Ok((id.to_string(), token.span)),
+                _ => Err(self.unexpected("identifier", Some(token)))
             },
-            _ => Some(Err(format!("Expected '{}' but EOF reached", expected)))
+            None => Err(self.unexpected("identifier", None))
         }
     }
+
+    fn expect(&mut self, expected: TokenType) -> Result<Span, Diagnostic> {
+        match self.next() {
+            Some(token) => if token.t_type == expected { Ok(token.span) } else {
+                Err(self.unexpected(&format!("'{}'", expected), Some(token)))
+            },
+            None => Err(self.unexpected(&format!("'{}'", expected), None))
+        }
+    }
+
+    fn unexpected(&self, expected: &str, found: Option<&Token>) -> Diagnostic {
+        match found {
+            Some(token) => Diagnostic::error(Phase::Parsing, code::UNEXPECTED_TOKEN,
+                                             format!("Expected {} but '{}' found", expected, token.t_type),
+                                             Some(token.span))
+                .with_label(format!("expected {}", expected)),
+            None => Diagnostic::error(Phase::Parsing, code::UNEXPECTED_EOF,
+                                      format!("Expected {} but EOF reached", expected),
+                                      Some(self.eof))
+                .with_label(format!("expected {}", expected))
+        }
+    }
+}
+
+fn resolve_type(type_name: String) -> Type {
+    match PREDEFINED_TYPES.get(type_name.as_str()) {
+        Some(found_type) => found_type.clone(),
+        None => Type::Custom(type_name)
+    }
+}
+
+//Every embedded expression is parsed on its own, the text around them becomes string literals
+fn parse_interpolation(segments: &[StringSegment], span: Span) -> Result<Expression, Diagnostic> {
+    let mut parts = Vec::new();
+    for segment in segments {
+        match segment {
+            StringSegment::Text(text) => parts.push(Expression::new(ExpressionKind::StringLiteral(text.clone()), span)),
+            StringSegment::Code(tokens) =>
+                parts.push(result_propagate_failure_to_result!(Parser::new(tokens).parse_single_expression()))
+        }
+    }
+    Ok(Expression::new(ExpressionKind::Interpolation(parts), span))
 }
 
-fn tilt_until(operators: &mut Vec<Token>, output: &mut Vec<Expression>, stop: TokenType) -> bool {
+fn ends_operand(t_type: &TokenType) -> bool {
+    matches!(t_type, TokenType::Id(_) | TokenType::IntegerLiteral(_) | TokenType::FloatLiteral(_) |
+                     TokenType::BooleanLiteral(_) | TokenType::StringLiteral(_) | TokenType::InterpolatedString(_) |
+                     TokenType::RRoundBracket | TokenType::RSquareBracket |
+                     //Only the end of a struct literal can be found in an expression
+                     TokenType::RCurlyBracket)
+}
+
+//Replaces the operands of an operator with the operation, like `1 +;` an operator can lack an operand
+fn reduce(output: &mut Vec<Expression>, operator: &Token) -> Result<(), Diagnostic> {
+    let missing_operand = || Diagnostic::error(Phase::Parsing, code::MALFORMED_EXPRESSION,
+                                               format!("Operator {} is missing an operand", operator_symbol(operator)),
+                                               Some(operator.span))
+        .with_label("missing operand".to_string());
+    match operator.t_type {
+        TokenType::Operator(op, _, _) => {
+            let (right, left) = match (output.pop(), output.pop()) {
+                (Some(right), Some(left)) => (right, left),
+                _ => return Err(missing_operand())
+            };
+            let span = left.span.to(right.span);
+            output.push(Expression::new(ExpressionKind::BinaryOperation(Box::new(left), op, Box::new(right)), span));
+        }
+        TokenType::UnaryOperator(op) => {
+            //The operand must follow the operator, an earlier one belongs to something else
+            let operand = match output.pop() {
+                Some(operand) if operand.span.start.offset >= operator.span.end.offset => operand,
+                _ => return Err(missing_operand())
+            };
+            let span = operator.span.to(operand.span);
+            output.push(Expression::new(ExpressionKind::UnaryOperation(op, Box::new(operand)), span));
+        }
+        _ => unreachable!("Only operators should be on the operator stack")
+    }
+    Ok(())
+}
+
+fn operator_symbol(operator: &Token) -> String {
+    match operator.t_type {
+        TokenType::Operator(op, _, _) => op.to_string(),
+        TokenType::UnaryOperator(op) => op.to_string(),
+        _ => operator.t_type.to_string()
+    }
+}
+
+fn tilt_until(operators: &mut Vec<Token>, output: &mut Vec<Expression>, stop: TokenType) -> Result<bool, Diagnostic> {
     while let Some(token) = operators.pop() {
         if token.t_type == stop {
-            return true;
-        }
-        match token.t_type {
-            TokenType::Operator(op, _, _) => {
-                let r = output.pop().unwrap();
-                let l = output.pop().unwrap();
-                output.push(Expression::BinaryOperation(Box::new(l),
-                                                        op,
-                                                        Box::new(r)));
-            }
-            _ => unreachable!("Only operators should be on the operator stack")
+            return Ok(true);
         }
+        result_propagate_failure_to_result!(reduce(output, &token));
     }
-    false
+    Ok(false)
 }
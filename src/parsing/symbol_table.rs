@@ -1,45 +1,181 @@
 use std::collections::HashMap;
 
-use crate::parsing::ast::Type;
+use crate::diagnostic::Span;
+use crate::execution::value::TypeDeclarations;
+use crate::parsing::ast::{FunctionSignature, Type};
 
+//Name of each variant of an enum with the types of its payload
+type Variants = Vec<(String, Vec<Type>)>;
+
+//Symbols declared by the host have no declaration in the source
+#[derive(Clone)]
+struct Symbol<T> {
+    value: T,
+    declaration: Option<Span>,
+}
+
+#[derive(Clone)]
 pub struct SymbolTable {
-    memory_frame: Vec<HashMap<String, Type>>
+    call_frames: Vec<Vec<HashMap<String, Symbol<Type>>>>,
+    functions: HashMap<String, Symbol<FunctionSignature>>,
+    //Fields of each struct, in declaration order
+    structs: HashMap<String, Symbol<Vec<(Type, String)>>>,
+    //Variants of each enum, in declaration order
+    enums: HashMap<String, Symbol<Variants>>,
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SymbolTable {
     pub fn new() -> Self {
         Self {
-            memory_frame: vec![HashMap::new()]
+            call_frames: vec![vec![HashMap::new()]],
+            functions: HashMap::new(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
         }
     }
 
     pub fn create_frame(&mut self) {
-        self.memory_frame.push(HashMap::new());
+        self.current_call_frame().push(HashMap::new());
     }
 
     pub fn remove_frame(&mut self) {
-        self.memory_frame.pop();
+        self.current_call_frame().pop();
+    }
+
+    //A call frame only sees its own scopes and the global one
+    pub fn create_call_frame(&mut self) {
+        self.call_frames.push(vec![HashMap::new()]);
     }
 
+    pub fn remove_call_frame(&mut self) {
+        self.call_frames.pop();
+    }
+
+    pub fn is_global_scope(&self) -> bool {
+        self.call_frames.len() == 1 && self.call_frames[0].len() == 1
+    }
 
-    pub fn declare(&mut self, key: String, value: Type) -> bool {
-        if self.retrieve_type(key.clone()).is_some() {
+    pub fn declare(&mut self, key: String, value: Type, declaration: Span) -> bool {
+        let current_frame = self.current_call_frame();
+        if current_frame.iter().any(|frame| frame.contains_key(&key)) {
             return false;
         }
-        let mut current_frame = self.memory_frame.pop().unwrap();
-        current_frame.insert(key.clone(), value);
-        self.memory_frame.push(current_frame);
+        current_frame.last_mut().unwrap().insert(key, Symbol { value, declaration: Some(declaration) });
         true
     }
 
-
     pub fn retrieve_type(&self, key: String) -> Option<Type> {
-        for frame in self.memory_frame.iter() {
-            match frame.get(&key) {
-                Some(value) => return Some(value.clone()),
-                _ => ()
+        self.lookup(&key).map(|symbol| symbol.value.clone())
+    }
+
+    pub fn retrieve_declaration(&self, key: String) -> Option<Span> {
+        self.lookup(&key).and_then(|symbol| symbol.declaration)
+    }
+
+    pub fn declare_global(&mut self, key: String, value: Type) -> bool {
+        if self.call_frames[0][0].contains_key(&key) {
+            return false;
+        }
+        self.call_frames[0][0].insert(key, Symbol { value, declaration: None });
+        true
+    }
+
+    pub fn retrieve_global_type(&self, key: String) -> Option<Type> {
+        self.call_frames[0][0].get(&key).map(|symbol| symbol.value.clone())
+    }
+
+    pub fn global_symbols(&self) -> Vec<(String, Type)> {
+        let mut symbols: Vec<(String, Type)> = self.call_frames[0][0].iter()
+            .map(|(key, symbol)| (key.clone(), symbol.value.clone()))
+            .collect();
+        symbols.sort_by(|(a, _), (b, _)| a.cmp(b));
+        symbols
+    }
+
+    pub fn declare_function(&mut self, key: String, signature: FunctionSignature, declaration: Option<Span>) -> bool {
+        if self.functions.contains_key(&key) {
+            return false;
+        }
+        self.functions.insert(key, Symbol { value: signature, declaration });
+        true
+    }
+
+    //Functions are declared ahead of the program, their signature is replaced once their types are resolved
+    pub fn redeclare_function(&mut self, key: String, signature: FunctionSignature) {
+        if let Some(symbol) = self.functions.get_mut(&key) {
+            symbol.value = signature;
+        }
+    }
+
+    pub fn retrieve_function(&self, key: String) -> Option<FunctionSignature> {
+        self.functions.get(&key).map(|symbol| symbol.value.clone())
+    }
+
+    pub fn retrieve_function_declaration(&self, key: String) -> Option<Span> {
+        self.functions.get(&key).and_then(|symbol| symbol.declaration)
+    }
+
+    //Structs and enums share the same names, a type cannot be both
+    pub fn is_type_declared(&self, key: &str) -> bool {
+        self.structs.contains_key(key) || self.enums.contains_key(key)
+    }
+
+    pub fn retrieve_type_declaration(&self, key: &str) -> Option<Span> {
+        self.structs.get(key).and_then(|symbol| symbol.declaration)
+            .or_else(|| self.enums.get(key).and_then(|symbol| symbol.declaration))
+    }
+
+    pub fn declare_struct(&mut self, key: String, fields: Vec<(Type, String)>, declaration: Span) -> bool {
+        if self.is_type_declared(&key) {
+            return false;
+        }
+        self.structs.insert(key, Symbol { value: fields, declaration: Some(declaration) });
+        true
+    }
+
+    pub fn retrieve_struct(&self, key: &str) -> Option<Vec<(Type, String)>> {
+        self.structs.get(key).map(|symbol| symbol.value.clone())
+    }
+
+    pub fn declare_enum(&mut self, key: String, variants: Variants, declaration: Span) -> bool {
+        if self.is_type_declared(&key) {
+            return false;
+        }
+        self.enums.insert(key, Symbol { value: variants, declaration: Some(declaration) });
+        true
+    }
+
+    pub fn retrieve_enum(&self, key: &str) -> Option<Variants> {
+        self.enums.get(key).map(|symbol| symbol.value.clone())
+    }
+
+    fn lookup(&self, key: &str) -> Option<&Symbol<Type>> {
+        let current_frame = self.call_frames.last().unwrap();
+        for frame in current_frame.iter().rev() {
+            if let Some(symbol) = frame.get(key) {
+                return Some(symbol);
             }
         }
-        None
+        self.call_frames[0][0].get(key)
     }
-}
\ No newline at end of file
+
+    fn current_call_frame(&mut self) -> &mut Vec<HashMap<String, Symbol<Type>>> {
+        self.call_frames.last_mut().unwrap()
+    }
+}
+
+impl TypeDeclarations for SymbolTable {
+    fn struct_fields(&self, id: &str) -> Option<&[(Type, String)]> {
+        self.structs.get(id).map(|symbol| symbol.value.as_slice())
+    }
+
+    fn enum_variants(&self, id: &str) -> Option<&[(String, Vec<Type>)]> {
+        self.enums.get(id).map(|symbol| symbol.value.as_slice())
+    }
+}
@@ -1,17 +1,30 @@
 use std::fmt::{Display, Formatter, Result};
 
-#[derive(Clone, PartialEq)]
+use crate::diagnostic::Span;
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum Operator {
     Add,
     Sub,
     Mul,
     Div,
+    Rem,
     Pow,
+    //Bitwise on integers
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    //Logical on booleans, the right operand is only evaluated when it decides the result
     And,
     Or,
     Eq,
+    Ne,
     Gt,
+    Ge,
     Lt,
+    Le,
 }
 
 impl Display for Operator {
@@ -21,12 +34,38 @@ impl Display for Operator {
             Operator::Sub => write!(f, "-"),
             Operator::Mul => write!(f, "*"),
             Operator::Div => write!(f, "/"),
-            Operator::Pow => write!(f, "^"),
-            Operator::And => write!(f, "&"),
-            Operator::Or => write!(f, "|"),
-            Operator::Eq => write!(f, "="),
+            Operator::Rem => write!(f, "%"),
+            Operator::Pow => write!(f, "**"),
+            Operator::BitAnd => write!(f, "&"),
+            Operator::BitOr => write!(f, "|"),
+            Operator::BitXor => write!(f, "^"),
+            Operator::Shl => write!(f, "<<"),
+            Operator::Shr => write!(f, ">>"),
+            Operator::And => write!(f, "&&"),
+            Operator::Or => write!(f, "||"),
+            Operator::Eq => write!(f, "=="),
+            Operator::Ne => write!(f, "!="),
             Operator::Gt => write!(f, ">"),
-            Operator::Lt => write!(f, "<")
+            Operator::Ge => write!(f, ">="),
+            Operator::Lt => write!(f, "<"),
+            Operator::Le => write!(f, "<=")
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum UnaryOperator {
+    Neg,
+    Not,
+    BitNot,
+}
+
+impl Display for UnaryOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            UnaryOperator::Neg => write!(f, "-"),
+            UnaryOperator::Not => write!(f, "!"),
+            UnaryOperator::BitNot => write!(f, "~")
         }
     }
 }
@@ -38,18 +77,46 @@ pub enum TokenType {
     RRoundBracket,
     LCurlyBracket,
     RCurlyBracket,
-    IntegerLiteral(i32),
+    LSquareBracket,
+    RSquareBracket,
+    //Up to 2147483648, which only fits in an int once negated, the parser checks the rest of the range
+    IntegerLiteral(u32),
     FloatLiteral(f32),
     BooleanLiteral(bool),
+    StringLiteral(String),
+    //String literal containing `{expression}` parts, kept apart from the plain ones
+    InterpolatedString(Vec<StringSegment>),
     Operator(Operator, u8, bool),
+    //Prefix only operators, a '-' is lexed as Sub and becomes Neg in prefix position
+    UnaryOperator(UnaryOperator),
     Print,
     Semicolon,
     Fn,
+    Struct,
+    Enum,
     Comma,
+    Dot,
+    //Separate the bounds of a range, '..=' includes the end
+    DotDot,
+    DotDotEq,
+    //Separates an enum from its variant, like in 'Shape::Circle'
+    DoubleColon,
     Assignment,
+    //Operator applied to the assigned variable, like '+='
+    CompoundAssignment(Operator),
+    Colon,
+    //Separates the pattern of a match arm from its body
+    Arrow,
     If,
+    Else,
+    Match,
     While,
     For,
+    In,
+    Step,
+    Break,
+    Continue,
+    Return,
 }
 
 impl Display for TokenType {
@@ -60,35 +127,59 @@ impl Display for TokenType {
             TokenType::RRoundBracket => write!(f, ")"),
             TokenType::LCurlyBracket => write!(f, "{{"),
             TokenType::RCurlyBracket => write!(f, "}}"),
+            TokenType::LSquareBracket => write!(f, "["),
+            TokenType::RSquareBracket => write!(f, "]"),
             TokenType::IntegerLiteral(_) => write!(f, "integer literal"),
             TokenType::FloatLiteral(_) => write!(f, "float literal"),
             TokenType::BooleanLiteral(_) => write!(f, "boolean literal"),
-            TokenType::Operator(_, _, _) => write!(f, "operator"),
+            TokenType::StringLiteral(_) | TokenType::InterpolatedString(_) => write!(f, "string literal"),
+            TokenType::Operator(_, _, _) | TokenType::UnaryOperator(_) => write!(f, "operator"),
             TokenType::Semicolon => write!(f, ";"),
             TokenType::Fn => write!(f, "fn"),
+            TokenType::Struct => write!(f, "struct"),
+            TokenType::Enum => write!(f, "enum"),
             TokenType::Comma => write!(f, ","),
+            TokenType::Dot => write!(f, "."),
+            TokenType::DotDot => write!(f, ".."),
+            TokenType::DotDotEq => write!(f, "..="),
+            TokenType::DoubleColon => write!(f, "::"),
             TokenType::Assignment => write!(f, ":="),
+            TokenType::CompoundAssignment(op) => write!(f, "{}=", op),
+            TokenType::Colon => write!(f, ":"),
+            TokenType::Arrow => write!(f, "=>"),
             TokenType::If => write!(f, "if"),
+            TokenType::Else => write!(f, "else"),
+            TokenType::Match => write!(f, "match"),
             TokenType::While => write!(f, "while"),
             TokenType::For => write!(f, "for"),
+            TokenType::In => write!(f, "in"),
+            TokenType::Step => write!(f, "step"),
+            TokenType::Break => write!(f, "break"),
+            TokenType::Continue => write!(f, "continue"),
             TokenType::Print => write!(f, "print"),
+            TokenType::Return => write!(f, "return"),
         }
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+pub enum StringSegment {
+    Text(String),
+    //Tokens of an embedded expression, without the surrounding braces
+    Code(Vec<Token>),
+}
+
+#[derive(Clone, PartialEq)]
 pub struct Token {
     pub t_type: TokenType,
-    pub line: u32,
-    pub char: u32,
+    pub span: Span,
 }
 
 impl Token {
-    pub fn new(t_type: TokenType, line: u32, char: u32) -> Self {
+    pub fn new(t_type: TokenType, span: Span) -> Self {
         Self {
             t_type,
-            line,
-            char,
+            span,
         }
     }
 }
@@ -0,0 +1,124 @@
+use std::io::{self, BufRead, Write};
+
+use thatlang::{Diagnostic, Engine, Phase, Renderer, Value};
+use thatlang::diagnostic::code;
+use thatlang::parsing::lexer::Lexer;
+use thatlang::parsing::token::{Token, TokenType};
+
+use crate::new_engine;
+
+const HELP: &str = ":type <expr>    Show the type of an expression without evaluating it
+:vars           List the declared variables with their type and value
+:reset          Forget every declared variable and function
+:help           Show this message
+:quit           Exit the REPL";
+
+pub struct Repl {
+    engine: Engine,
+    color: bool,
+}
+
+impl Repl {
+    pub fn new(color: bool) -> Self {
+        Self {
+            engine: new_engine(),
+            color,
+        }
+    }
+
+    pub fn run(&mut self) {
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        let mut buffer = String::new();
+        loop {
+            print!("{}", if buffer.is_empty() { ">> " } else { ".. " });
+            io::stdout().flush().unwrap();
+            let line = match lines.next() {
+                Some(Ok(line)) => line,
+                _ => break
+            };
+            if buffer.is_empty() && line.trim_start().starts_with(':') {
+                if !self.meta_command(line.trim()) {
+                    break;
+                }
+                continue;
+            }
+            buffer.push_str(&line);
+            buffer.push('\n');
+            let tokens = match Lexer::new(&buffer).lex() {
+                Ok(tokens) => tokens,
+                //Keep reading until the comment is closed
+                Err(error) if error.code == code::UNTERMINATED_COMMENT => continue,
+                Err(error) => {
+                    self.report(&buffer, &[error]);
+                    buffer.clear();
+                    continue;
+                }
+            };
+            //Keep reading until every block has been closed
+            if open_blocks(&tokens) > 0 {
+                continue;
+            }
+            let source = std::mem::take(&mut buffer);
+            if !tokens.is_empty() {
+                self.evaluate(&source);
+            }
+        }
+    }
+
+    //Returns false when the REPL must be closed
+    fn meta_command(&mut self, command: &str) -> bool {
+        let (name, argument) = match command.find(char::is_whitespace) {
+            Some(index) => (&command[..index], command[index..].trim()),
+            None => (command, "")
+        };
+        match name {
+            ":type" => match self.engine.type_of(argument) {
+                Ok(e_type) => println!("{}", e_type),
+                Err(errors) => self.report(argument, &errors)
+            },
+            ":vars" => {
+                for (id, v_type, value) in self.engine.globals() {
+                    println!("{}: {} = {}", id, v_type, value.printable());
+                }
+            }
+            ":reset" => self.engine = new_engine(),
+            ":help" => println!("{}", HELP),
+            ":quit" | ":q" => return false,
+            unexpected => eprintln!("Unknown command '{}', type :help for the list of commands", unexpected)
+        }
+        true
+    }
+
+    fn evaluate(&mut self, source: &str) {
+        //Bare expressions are evaluated and their value is printed
+        let result = match self.engine.eval_expression(source) {
+            Ok(Value::Unit) => Ok(()),
+            Ok(value) => {
+                println!("{}", value.printable());
+                Ok(())
+            }
+            Err(errors) if errors[0].phase == Phase::Parsing => self.engine.eval(source),
+            Err(errors) => Err(errors)
+        };
+        self.report(source, self.engine.warnings());
+        if let Err(errors) = result {
+            self.report(source, &errors);
+        }
+    }
+
+    fn report(&self, source: &str, diagnostics: &[Diagnostic]) {
+        let renderer = Renderer::new(source, "<repl>").with_color(self.color);
+        for diagnostic in diagnostics {
+            eprint!("{}", renderer.render(diagnostic));
+        }
+    }
+}
+
+fn open_blocks(tokens: &[Token]) -> i32 {
+    tokens.iter().map(|token| match token.t_type {
+        TokenType::LCurlyBracket => 1,
+        TokenType::RCurlyBracket => -1,
+        _ => 0
+    }).sum()
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use thatlang::diagnostic::code;
+use thatlang::Value;
+
+use common::{error_codes, run_for};
+
+#[test]
+fn overflow_is_a_runtime_error() {
+    let source = "int big := 2147483647; int one := 1; int x := big + one;";
+    assert_eq!(error_codes(source), vec![code::INTEGER_OVERFLOW]);
+}
+
+#[test]
+fn constant_overflow_is_rejected_by_the_analyzer() {
+    assert_eq!(error_codes("int x := 2147483647 + 1;"), vec![code::CONSTANT_OVERFLOW]);
+}
+
+#[test]
+fn wrapping_and_saturating_builtins_do_not_fail() {
+    let source = "int big := 2147483647;
+                  int wrapped := wrapping_add(big, 1);
+                  int saturated := saturating_add(big, 1);";
+    assert_eq!(run_for(source, "wrapped"), Value::IntegerValue(i32::MIN));
+    assert_eq!(run_for(source, "saturated"), Value::IntegerValue(i32::MAX));
+}
+
+#[test]
+fn smallest_int_can_be_written_as_a_literal() {
+    assert_eq!(run_for("int min := -2147483648;", "min"), Value::IntegerValue(i32::MIN));
+    assert_eq!(error_codes("int x := 2147483648;"), vec![code::LITERAL_OUT_OF_RANGE]);
+    assert_eq!(error_codes("int x := -(2147483648);"), vec![code::LITERAL_OUT_OF_RANGE]);
+    assert_eq!(error_codes("int x := -2147483649;"), vec![code::LITERAL_OUT_OF_RANGE]);
+}
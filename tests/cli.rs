@@ -0,0 +1,54 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn thatlang(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_thatlang"))
+        .args(args)
+        .arg("--color=never")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Unable to start thatlang");
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn exit_code(args: &[&str]) -> i32 {
+    thatlang(args, "").status.code().unwrap()
+}
+
+#[test]
+fn run_inline_program_prints_its_output() {
+    let output = thatlang(&["run", "-e", "print 6 * 7;"], "");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "42\n");
+}
+
+#[test]
+fn run_reads_the_program_from_stdin() {
+    let output = thatlang(&["run", "-"], "int x := 2; print x + 1;");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n");
+}
+
+#[test]
+fn check_does_not_execute_the_program() {
+    let output = thatlang(&["check", "-e", "print 1 / 0;"], "");
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn each_failing_phase_has_its_own_exit_code() {
+    assert_eq!(exit_code(&["frobnicate"]), 1);
+    assert_eq!(exit_code(&["run", "-e", "print $;"]), 2);
+    assert_eq!(exit_code(&["run", "-e", "int x 3;"]), 3);
+    assert_eq!(exit_code(&["run", "-e", "int x := true;"]), 4);
+    assert_eq!(exit_code(&["run", "-e", "int x := 0; print 1 / x;"]), 5);
+}
+
+#[test]
+fn check_rejects_a_condition_that_is_not_a_boolean() {
+    assert_eq!(exit_code(&["check", "-e", "if 1 { } else { }"]), 4);
+}
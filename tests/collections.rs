@@ -0,0 +1,96 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use thatlang::diagnostic::code;
+use thatlang::Value;
+
+use common::{error_codes, run_for};
+
+fn integers(values: &[i32]) -> Value {
+    Value::ArrayValue(values.iter().map(|&value| Value::IntegerValue(value)).collect())
+}
+
+#[test]
+fn arrays_grow_shrink_and_are_indexed() {
+    let source = "int[][] grid;
+                  grid.push([1, 2]);
+                  grid[0].push(3);
+                  grid[0][1] := 5;
+                  int last := grid[0].pop();
+                  int[] row := grid[0];";
+    assert_eq!(run_for(source, "last"), Value::IntegerValue(3));
+    assert_eq!(run_for(source, "row"), integers(&[1, 5]));
+}
+
+#[test]
+fn arrays_are_copied_on_assignment() {
+    let source = "int[] xs := [1, 2]; int[] ys := xs; ys[0] := 9; ys.push(3);";
+    assert_eq!(run_for(source, "xs"), integers(&[1, 2]));
+    assert_eq!(run_for(source, "ys"), integers(&[9, 2, 3]));
+}
+
+#[test]
+fn reading_past_the_end_is_a_runtime_error() {
+    assert_eq!(error_codes("int[] xs := [1]; int x := xs[1];"), vec![code::INDEX_OUT_OF_BOUNDS]);
+    assert_eq!(error_codes("int[] xs; int x := xs.pop();"), vec![code::INDEX_OUT_OF_BOUNDS]);
+}
+
+//Elements are read and changed in place, a loop over an array does not copy it at each step
+#[test]
+fn large_arrays_are_updated_in_place() {
+    let source = "int[] xs;
+                  for i in 0..50000 { xs.push(i); }
+                  int total := 0;
+                  for i in 0..50000 { total += xs[i] % 7; xs[i] := 0; }";
+    let start = Instant::now();
+    assert_eq!(run_for(source, "total"), Value::IntegerValue(149997));
+    assert!(start.elapsed() < Duration::from_secs(20));
+}
+
+#[test]
+fn element_type_is_unified_across_the_literal() {
+    assert_eq!(run_for("int[][] xs := [[], [1]];", "xs"),
+               Value::ArrayValue(vec![integers(&[]), integers(&[1])]));
+    assert_eq!(error_codes("string[][] xs := [[], [1]];"), vec![code::MISMATCHED_TYPES]);
+    assert_eq!(error_codes("int[] xs := [1, true];"), vec![code::MISMATCHED_TYPES]);
+}
+
+#[test]
+fn iterated_elements_must_have_a_known_type() {
+    assert_eq!(error_codes("for x in [] { int y := x + true; }"), vec![code::NOT_ITERABLE]);
+    assert_eq!(error_codes("for x in [[]] { }"), vec![code::NOT_ITERABLE]);
+    assert!(error_codes("int[] xs := []; for x in xs { int y := x + 1; }").is_empty());
+}
+
+#[test]
+fn strings_arrays_and_maps_are_measured_alike() {
+    let source = r#"string s := "héllo"; int[] xs := [1, 2]; map<int, bool> m := [1: true];
+                    int total := s.len() * 100 + xs.len() * 10 + m.len();"#;
+    assert_eq!(run_for(source, "total"), Value::IntegerValue(521));
+    assert_eq!(error_codes(r#"int n := len("abc");"#), vec![code::UNDECLARED_FUNCTION]);
+}
+
+#[test]
+fn maps_keep_their_entries_ordered_by_key() {
+    let source = r#"map<string, int> ages := ["bob": 31, "alice": 27];
+                    ages.insert("carol", 45);
+                    bool removed := ages.remove("bob");
+                    string[] names := ages.keys();
+                    int alice := ages.get("alice");"#;
+    assert_eq!(run_for(source, "removed"), Value::BooleanValue(true));
+    assert_eq!(run_for(source, "names"), Value::ArrayValue(vec![
+        Value::StringValue("alice".to_string()),
+        Value::StringValue("carol".to_string()),
+    ]));
+    assert_eq!(run_for(source, "alice"), Value::IntegerValue(27));
+    assert_eq!(error_codes(r#"map<string, int> m; int x := m.get("dave");"#), vec![code::MISSING_KEY]);
+}
+
+#[test]
+fn entry_types_are_unified_across_the_literal() {
+    assert_eq!(error_codes(r#"map<string, int[]> m := ["a": [], "b": ["x"]];"#), vec![code::MISMATCHED_TYPES]);
+    assert!(error_codes(r#"map<string, int[]> m := ["a": [], "b": [1]];"#).is_empty());
+    assert_eq!(error_codes("map<int, int> m := [1: 2, true: 3];"), vec![code::MISMATCHED_TYPES]);
+    assert_eq!(error_codes("map<int[], int> m;"), vec![code::UNHASHABLE_KEY]);
+}
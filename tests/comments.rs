@@ -0,0 +1,33 @@
+mod common;
+
+use thatlang::diagnostic::code;
+use thatlang::{Engine, Value};
+
+use common::run_for;
+
+#[test]
+fn line_comment_ends_at_the_end_of_the_line() {
+    let source = "int x := 1; // x := 2;\nint y := x + 1;";
+    assert_eq!(run_for(source, "y"), Value::IntegerValue(2));
+}
+
+#[test]
+fn block_comments_nest() {
+    let source = "int x := /* outer /* inner */ still a comment */ 3;";
+    assert_eq!(run_for(source, "x"), Value::IntegerValue(3));
+}
+
+#[test]
+fn positions_are_tracked_across_comments() {
+    let errors = Engine::new().eval("/* one\ntwo */ int x := // three\n true;").unwrap_err();
+    let span = errors[0].span.unwrap();
+    assert_eq!((span.start.line, span.start.column), (3, 2));
+}
+
+#[test]
+fn unterminated_comment_points_at_its_opening() {
+    let errors = Engine::new().eval("int x := 1;\n  /* never /* closed */").unwrap_err();
+    assert_eq!(errors[0].code, code::UNTERMINATED_COMMENT);
+    let span = errors[0].span.unwrap();
+    assert_eq!((span.start.line, span.start.column), (2, 3));
+}
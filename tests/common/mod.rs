@@ -0,0 +1,30 @@
+#![allow(dead_code)]
+
+use thatlang::{Engine, Value};
+
+//Runs `source` on a new engine, failing the test with the reported errors if it does not succeed
+pub fn run(source: &str) -> Engine {
+    let mut engine = Engine::new();
+    if let Err(errors) = engine.eval(source) {
+        let messages: Vec<String> = errors.into_iter().map(|error| format!("{}: {}", error.code, error.message)).collect();
+        panic!("{}", messages.join("\n"));
+    }
+    engine
+}
+
+pub fn global(engine: &Engine, id: &str) -> Value {
+    engine.get_global(id).unwrap_or_else(|| panic!("global {} is not declared", id))
+}
+
+//Value of the global `id` after running `source`
+pub fn run_for(source: &str, id: &str) -> Value {
+    global(&run(source), id)
+}
+
+//Codes of the errors reported when evaluating `source`, empty if it succeeds
+pub fn error_codes(source: &str) -> Vec<&'static str> {
+    match Engine::new().eval(source) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.into_iter().map(|error| error.code).collect()
+    }
+}
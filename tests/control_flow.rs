@@ -0,0 +1,105 @@
+mod common;
+
+use thatlang::diagnostic::code;
+use thatlang::Value;
+
+use common::{error_codes, run_for};
+
+fn classify(n: i32) -> Value {
+    let source = format!("int n := {};
+                          string kind;
+                          if n < 0 {{
+                              kind := \"negative\";
+                          }} else if n == 0 {{
+                              kind := \"zero\";
+                          }} else {{
+                              kind := \"positive\";
+                          }}", n);
+    run_for(&source, "kind")
+}
+
+#[test]
+fn chained_else_if_takes_the_first_true_branch() {
+    assert_eq!(classify(-4), Value::StringValue("negative".to_string()));
+    assert_eq!(classify(0), Value::StringValue("zero".to_string()));
+    assert_eq!(classify(7), Value::StringValue("positive".to_string()));
+}
+
+#[test]
+fn each_branch_has_its_own_scope() {
+    let source = "int x := 0;
+                  if x > 0 { int y := 1; x := y; } else { int y := 2; x := y; }";
+    assert_eq!(run_for(source, "x"), Value::IntegerValue(2));
+    assert_eq!(error_codes("if true { int y := 1; } else { y := 2; }"), vec![code::UNDECLARED_VARIABLE]);
+}
+
+#[test]
+fn break_and_continue_target_the_innermost_loop() {
+    let source = "int sum := 0;
+                  for int i := 0; i < 10; i += 1 {
+                      if i % 2 == 0 { continue; }
+                      if i > 7 { break; }
+                      sum += i;
+                  }";
+    assert_eq!(run_for(source, "sum"), Value::IntegerValue(1 + 3 + 5 + 7));
+}
+
+#[test]
+fn labels_target_an_outer_loop() {
+    let source = "int pairs := 0;
+                  outer: for int i := 0; i < 5; i += 1 {
+                      int j := 0;
+                      while true {
+                          j += 1;
+                          if j > i { continue outer; }
+                          if i == 3 { break outer; }
+                          pairs += 1;
+                      }
+                  }";
+    assert_eq!(run_for(source, "pairs"), Value::IntegerValue(1 + 2));
+}
+
+#[test]
+fn break_outside_a_loop_or_to_an_unknown_label_is_rejected() {
+    assert_eq!(error_codes("break;"), vec![code::OUTSIDE_LOOP]);
+    assert_eq!(error_codes("while true { continue inner; }"), vec![code::UNDECLARED_LABEL]);
+}
+
+#[test]
+fn ranges_count_towards_their_end_by_step() {
+    let source = "int[] up; int[] down;
+                  for i in 0..10 step 4 { up.push(i); }
+                  for i in 3..=1 step -1 { down.push(i); }";
+    let integers = |values: &[i32]| Value::ArrayValue(values.iter().map(|&value| Value::IntegerValue(value)).collect());
+    assert_eq!(run_for(source, "up"), integers(&[0, 4, 8]));
+    assert_eq!(run_for(source, "down"), integers(&[3, 2, 1]));
+}
+
+#[test]
+fn range_stops_at_the_largest_integer() {
+    assert_eq!(run_for("int count := 0; for i in 2147483646..=2147483647 { count += 1; }", "count"),
+               Value::IntegerValue(2));
+}
+
+#[test]
+fn collections_bind_their_elements_and_entries() {
+    let source = r#"string joined := "";
+                    for c in "ab" { joined += c; }
+                    for (key, value) in [2: "y", 1: "x"] { joined += "{key}{value}"; }"#;
+    assert_eq!(run_for(source, "joined"), Value::StringValue("ab1x2y".to_string()));
+}
+
+#[test]
+fn invalid_ranges_are_rejected() {
+    assert_eq!(error_codes("for i in 0..3 step 0 { }"), vec![code::ZERO_STEP]);
+    assert_eq!(error_codes("int zero := 0; for i in 0..3 step zero { }"), vec![code::INVALID_STEP]);
+    assert_eq!(error_codes("int n; for i in 0..n { }"), vec![code::UNASSIGNED_VARIABLE]);
+}
+
+#[test]
+fn conditions_must_be_booleans() {
+    assert_eq!(error_codes("if 1 { } else { }"), vec![code::MISMATCHED_TYPES]);
+    assert_eq!(error_codes("if false { } else if \"yes\" { }"), vec![code::MISMATCHED_TYPES]);
+    assert_eq!(error_codes("while 0 { }"), vec![code::MISMATCHED_TYPES]);
+    assert_eq!(error_codes("for int i := 0; i; i += 1 { }"), vec![code::MISMATCHED_TYPES]);
+}
@@ -0,0 +1,69 @@
+use thatlang::diagnostic::code;
+use thatlang::{Engine, Phase, Renderer, Severity};
+
+#[test]
+fn lexer_error_points_at_the_character() {
+    let errors = Engine::new().eval("int x := 1;\nprint x $ 2;").unwrap_err();
+    assert_eq!(errors[0].phase, Phase::Lexing);
+    assert_eq!(errors[0].code, code::UNEXPECTED_CHARACTER);
+    let span = errors[0].span.unwrap();
+    assert_eq!((span.start.line, span.start.column), (2, 9));
+}
+
+#[test]
+fn unicode_digits_are_not_part_of_a_number() {
+    let errors = Engine::new().eval("print 1.²;").unwrap_err();
+    assert_eq!(errors[0].code, code::UNEXPECTED_CHARACTER);
+    let errors = Engine::new().eval("print 1٣;").unwrap_err();
+    assert_eq!(errors[0].code, code::UNEXPECTED_CHARACTER);
+}
+
+#[test]
+fn analyzer_error_spans_the_offending_expression() {
+    let source = "int x := 1 + true;";
+    let errors = Engine::new().eval(source).unwrap_err();
+    assert_eq!(errors[0].phase, Phase::Analysis);
+    assert_eq!(errors[0].severity, Severity::Error);
+    let span = errors[0].span.unwrap();
+    assert_eq!(&source[span.start.offset..span.end.offset], "1 + true");
+}
+
+fn render(source: &str, color: bool) -> String {
+    let errors = Engine::new().eval(source).unwrap_err();
+    Renderer::new(source, "test.mps").with_color(color).render(&errors[0])
+}
+
+#[test]
+fn report_shows_the_source_under_the_message() {
+    let report = render("int x := 1;\nx := true;", false);
+    assert_eq!(report, "error[E0303]: Mismatched types variable x was declared Integer but assigned Boolean
+ --> test.mps:2:6
+  |
+1 | int x := 1;
+  | ----------- variable `x` declared here as Integer
+2 | x := true;
+  |      ^^^^ expected Integer, found Boolean
+");
+}
+
+#[test]
+fn report_is_colored_only_on_request() {
+    assert!(!render("int x := true;", false).contains('\u{1b}'));
+    assert!(render("int x := true;", true).contains('\u{1b}'));
+}
+
+fn codes(source: &str) -> Vec<&'static str> {
+    Engine::new().eval(source).unwrap_err().into_iter().map(|error| error.code).collect()
+}
+
+#[test]
+fn parser_reports_every_invalid_statement() {
+    assert_eq!(codes("int x 1;\nprint 2;\nint y 3;"), vec![code::UNEXPECTED_TOKEN, code::UNEXPECTED_TOKEN]);
+}
+
+#[test]
+fn analyzer_reports_every_error_without_cascades() {
+    //y is declared even though its value is invalid, so its uses are not reported
+    let source = "int y := 1 + true;\nprint y * 2;\nbool z := 3;\nprint w;";
+    assert_eq!(codes(source), vec![code::INVALID_OPERANDS, code::MISMATCHED_TYPES, code::UNDECLARED_VARIABLE]);
+}
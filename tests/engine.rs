@@ -0,0 +1,190 @@
+use thatlang::diagnostic::code;
+use thatlang::{Engine, Phase, Type, Value};
+
+#[test]
+fn compiled_program_runs_and_exposes_its_globals() {
+    let mut engine = Engine::new();
+    let program = engine.compile("int x := 20; int y := x + 1;").unwrap();
+    engine.run(program).unwrap();
+    assert_eq!(engine.get_global("y"), Some(Value::IntegerValue(21)));
+}
+
+#[test]
+fn host_globals_are_visible_to_scripts() {
+    let mut engine = Engine::new();
+    engine.set_global("limit", Type::Integer, Value::IntegerValue(4)).unwrap();
+    engine.eval("int total := 0; for int i := 0; i < limit; i += 1 { total += i; }").unwrap();
+    assert_eq!(engine.get_global("total"), Some(Value::IntegerValue(6)));
+}
+
+#[test]
+fn global_cannot_change_type() {
+    let mut engine = Engine::new();
+    engine.set_global("x", Type::Integer, Value::IntegerValue(1)).unwrap();
+    let error = engine.set_global("x", Type::Boolean, Value::BooleanValue(true)).unwrap_err();
+    assert_eq!(error.code, code::MISMATCHED_TYPES);
+}
+
+#[test]
+fn errors_are_returned_with_their_phase() {
+    let mut engine = Engine::new();
+    let errors = engine.eval("int x := true;").unwrap_err();
+    assert_eq!(errors[0].phase, Phase::Analysis);
+    assert_eq!(errors[0].code, code::MISMATCHED_TYPES);
+}
+
+#[test]
+fn declarations_of_an_invalid_program_are_forgotten() {
+    let mut engine = Engine::new();
+    assert!(engine.eval("int x := 1; bool y := 2;").is_err());
+    engine.eval("int x := 2;").unwrap();
+    assert_eq!(engine.get_global("x"), Some(Value::IntegerValue(2)));
+}
+
+#[test]
+fn globals_of_a_program_failing_at_runtime_are_forgotten() {
+    let mut engine = Engine::new();
+    engine.eval("int kept := 1;").unwrap();
+    let errors = engine.eval("int zero := 0; kept := 2; int x := kept / zero;").unwrap_err();
+    assert_eq!(errors[0].code, code::DIVISION_BY_ZERO);
+    assert_eq!(engine.get_global("zero"), None);
+    assert_eq!(engine.globals(), vec![("kept".to_string(), Type::Integer, Value::IntegerValue(2))]);
+    engine.eval(r#"string zero := "0";"#).unwrap();
+    assert_eq!(engine.get_global("zero"), Some(Value::StringValue("0".to_string())));
+}
+
+#[test]
+fn expressions_are_evaluated_and_typed() {
+    let mut engine = Engine::new();
+    engine.eval("int x := 3;").unwrap();
+    assert_eq!(engine.eval_expression("x * 2").unwrap(), Value::IntegerValue(6));
+    assert_eq!(engine.type_of("x > 2").unwrap(), Type::Boolean);
+    assert_eq!(engine.globals(), vec![("x".to_string(), Type::Integer, Value::IntegerValue(3))]);
+}
+
+#[test]
+fn unit_global_can_be_read() {
+    let mut engine = Engine::new();
+    engine.set_global("nothing", Type::Unit, Value::Unit).unwrap();
+    engine.eval("unit copy := nothing;").unwrap();
+    assert_eq!(engine.get_global("copy"), Some(Value::Unit));
+}
+
+fn engine_with_clamp() -> Engine {
+    let mut engine = Engine::new();
+    engine.register_function("clamp", vec![Type::Integer, Type::Integer], Type::Integer, |arguments| {
+        match arguments {
+            [Value::IntegerValue(value), Value::IntegerValue(max)] if max >= value => Ok(Value::IntegerValue(*value)),
+            [Value::IntegerValue(_), Value::IntegerValue(max)] if *max >= 0 => Ok(Value::IntegerValue(*max)),
+            _ => Err("negative maximum".to_string())
+        }
+    }).unwrap();
+    engine
+}
+
+#[test]
+fn native_function_is_called_by_scripts() {
+    let mut engine = engine_with_clamp();
+    engine.eval("int x := clamp(12, 10);").unwrap();
+    assert_eq!(engine.get_global("x"), Some(Value::IntegerValue(10)));
+}
+
+#[test]
+fn calls_to_native_functions_are_type_checked() {
+    let errors = engine_with_clamp().eval("int x := clamp(true, 10);").unwrap_err();
+    assert_eq!(errors[0].code, code::MISMATCHED_TYPES);
+}
+
+#[test]
+fn native_failure_is_a_runtime_error() {
+    let errors = engine_with_clamp().eval("int x := clamp(1, -1);").unwrap_err();
+    assert_eq!(errors[0].code, code::RUNTIME_FAILURE);
+    assert!(errors[0].message.contains("negative maximum"));
+}
+
+#[test]
+fn native_function_cannot_be_registered_twice() {
+    let mut engine = engine_with_clamp();
+    let error = engine.register_function("clamp", vec![], Type::Unit, |_| Ok(Value::Unit)).unwrap_err();
+    assert_eq!(error.code, code::ALREADY_DECLARED);
+}
+
+#[test]
+fn empty_global_keeps_its_declared_element_type() {
+    let mut engine = Engine::new();
+    engine.set_global("names", Type::Array(Box::new(Type::String)), Value::ArrayValue(Vec::new())).unwrap();
+    let errors = engine.eval("names.push(1);").unwrap_err();
+    assert_eq!(errors[0].code, code::MISMATCHED_TYPES);
+}
+
+#[test]
+fn global_must_have_its_declared_type() {
+    let mut engine = Engine::new();
+    let mixed = Value::ArrayValue(vec![Value::IntegerValue(1), Value::BooleanValue(true)]);
+    let error = engine.set_global("xs", Type::Array(Box::new(Type::Integer)), mixed).unwrap_err();
+    assert_eq!(error.code, code::MISMATCHED_TYPES);
+    let error = engine.set_global("xs", Type::Array(Box::new(Type::Error)), Value::ArrayValue(Vec::new())).unwrap_err();
+    assert_eq!(error.code, code::UNKNOWN_TYPE);
+}
+
+#[test]
+fn native_function_can_return_an_empty_array() {
+    let mut engine = Engine::new();
+    engine.register_function("none", vec![], Type::Array(Box::new(Type::Integer)), |_| Ok(Value::ArrayValue(Vec::new())))
+        .unwrap();
+    engine.eval("int[] xs := none();").unwrap();
+    assert_eq!(engine.get_global("xs"), Some(Value::ArrayValue(Vec::new())));
+}
+
+#[test]
+fn native_return_value_is_checked_against_its_declared_type() {
+    let mut engine = Engine::new();
+    engine.register_function("mixed", vec![], Type::Array(Box::new(Type::Integer)),
+                             |_| Ok(Value::ArrayValue(vec![Value::IntegerValue(1), Value::BooleanValue(true)])))
+        .unwrap();
+    let errors = engine.eval("int[] xs := mixed();").unwrap_err();
+    assert_eq!(errors[0].code, code::RUNTIME_FAILURE);
+}
+
+#[test]
+fn native_function_never_receives_an_unassigned_variable() {
+    let errors = engine_with_clamp().eval("int x; int y := clamp(x, 10);").unwrap_err();
+    assert_eq!(errors[0].code, code::UNASSIGNED_VARIABLE);
+}
+
+#[test]
+fn struct_global_is_checked_against_its_declaration() {
+    let mut engine = Engine::new();
+    engine.eval("struct P { int x, }").unwrap();
+    let p = |x| Value::StructValue("P".to_string(), vec![("x".to_string(), x)]);
+    let error = engine.set_global("p", Type::Custom("P".to_string()), p(Value::StringValue("3".to_string()))).unwrap_err();
+    assert_eq!(error.code, code::MISMATCHED_TYPES);
+    engine.set_global("p", Type::Custom("P".to_string()), p(Value::IntegerValue(3))).unwrap();
+    engine.eval("int n := 0; for i in 0..p.x { n += 1; }").unwrap();
+    assert_eq!(engine.get_global("n"), Some(Value::IntegerValue(3)));
+}
+
+#[test]
+fn global_of_an_undeclared_type_is_rejected() {
+    let mut engine = Engine::new();
+    let error = engine.set_global("qs", Type::Array(Box::new(Type::Custom("Q".to_string()))), Value::ArrayValue(Vec::new()))
+        .unwrap_err();
+    assert_eq!(error.code, code::UNKNOWN_TYPE);
+}
+
+#[test]
+fn native_enum_is_checked_against_its_variant() {
+    let mut engine = Engine::new();
+    engine.eval("enum E { A(int), B, }").unwrap();
+    engine.register_function("make", vec![], Type::Custom("E".to_string()),
+                             |_| Ok(Value::EnumValue("E".to_string(), "A".to_string(), Vec::new())))
+        .unwrap();
+    let errors = engine.eval("int x := match make() { E::A(n) => n, E::B => 0, };").unwrap_err();
+    assert_eq!(errors[0].code, code::RUNTIME_FAILURE);
+}
+
+#[test]
+fn values_of_an_unexpected_type_are_errors_in_native_functions() {
+    let error = Value::StringValue("3".to_string()).expect_int().unwrap_err();
+    assert_eq!(error.code, code::INVALID_OPERATION);
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use thatlang::diagnostic::code;
+use thatlang::Value;
+
+use common::{error_codes, run_for};
+
+const SHAPE: &str = "enum Shape { Circle(int), Rect(int, int), Empty, }";
+
+#[test]
+fn match_expression_binds_the_payload() {
+    let source = format!("{} Fn area(Shape shape) int {{
+                              return match shape {{
+                                  Shape::Circle(r) => 3 * r * r,
+                                  Shape::Rect(w, h) => w * h,
+                                  Shape::Empty => 0,
+                              }};
+                          }}
+                          int total := area(Shape::Circle(2)) + area(Shape::Rect(2, 5)) + area(Shape::Empty);", SHAPE);
+    assert_eq!(run_for(&source, "total"), Value::IntegerValue(22));
+}
+
+#[test]
+fn match_statement_takes_the_first_accepting_arm() {
+    let source = format!("{} string kind;
+                          match Shape::Rect(3, 3) {{
+                              Shape::Circle(_) => {{ kind := \"circle\"; }}
+                              Shape::Rect(w, _) => {{ kind := \"rect of width {{w}}\"; }}
+                              _ => {{ kind := \"other\"; }}
+                          }}", SHAPE);
+    assert_eq!(run_for(&source, "kind"), Value::StringValue("rect of width 3".to_string()));
+}
+
+#[test]
+fn match_must_be_exhaustive() {
+    let source = format!("{} int x := match Shape::Empty {{ Shape::Empty => 0, Shape::Circle(r) => r, }};", SHAPE);
+    assert_eq!(error_codes(&source), vec![code::NON_EXHAUSTIVE_MATCH]);
+}
+
+#[test]
+fn arm_after_a_catch_all_is_unreachable() {
+    let source = format!("{} int x := match Shape::Empty {{ _ => 0, Shape::Empty => 1, }};", SHAPE);
+    assert_eq!(error_codes(&source), vec![code::UNREACHABLE_ARM]);
+}
+
+#[test]
+fn unknown_variant_is_rejected() {
+    assert_eq!(error_codes(&format!("{} Shape s := Shape::Triangle;", SHAPE)), vec![code::UNKNOWN_VARIANT]);
+}
+
+#[test]
+fn match_on_an_enum_without_variants_needs_no_arm() {
+    let source = "enum Never {}
+                  Fn absurd(Never never) int { match never { } return match never { }; }";
+    assert!(error_codes(source).is_empty());
+    assert_eq!(error_codes("enum Never {} Fn absurd(Never never) { match never { _ => { } } }"),
+               vec![code::UNREACHABLE_ARM]);
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use std::thread;
+
+use thatlang::diagnostic::code;
+use thatlang::{Engine, Phase};
+
+use common::error_codes;
+
+//Each level goes through nested blocks, like real recursive scripts do
+const RECURSION: &str = "Fn depth(int n) int {
+    if n == 0 {
+        return 0;
+    }
+    int result := 0;
+    while result == 0 {
+        for int i := 0; i < 1; i += 1 {
+            if true {
+                result := depth(n - 1) + 1;
+            }
+        }
+    }
+    return result;
+}";
+
+//Like a host calling the engine from its main thread
+fn on_main_sized_stack<T: Send + 'static>(run: impl FnOnce() -> T + Send + 'static) -> T {
+    thread::Builder::new().stack_size(8 * 1024 * 1024).spawn(run).unwrap().join().unwrap()
+}
+
+#[test]
+fn runtime_error_carries_its_span_and_trace() {
+    let source = "Fn divide(int a, int b) int { return a / b; }\nint x := divide(1, 0);";
+    let errors = Engine::new().eval(source).unwrap_err();
+    assert_eq!(errors[0].phase, Phase::Runtime);
+    assert_eq!(errors[0].code, code::DIVISION_BY_ZERO);
+    assert_eq!(errors[0].span.unwrap().start.line, 1);
+    assert_eq!(errors[0].notes.len(), 1);
+}
+
+#[test]
+fn engine_survives_a_runtime_error() {
+    let mut engine = Engine::new();
+    assert!(engine.eval("int x := 1 / 0;").is_err());
+    engine.eval("int y := 2;").unwrap();
+}
+
+#[test]
+fn recursion_fits_in_a_main_thread_stack() {
+    let result = on_main_sized_stack(|| {
+        let mut engine = Engine::new();
+        engine.eval(&format!("{} int x := depth(40);", RECURSION)).map(|_| engine.get_global("x"))
+    });
+    assert_eq!(result.map_err(|errors| errors[0].code), Ok(Some(thatlang::Value::IntegerValue(40))));
+}
+
+#[test]
+fn recursion_too_deep_for_the_stack_is_a_runtime_error() {
+    let codes = on_main_sized_stack(|| error_codes(&format!("{} int x := depth(1000000);", RECURSION)));
+    assert_eq!(codes, vec![code::STACK_OVERFLOW]);
+}
+
+#[test]
+fn stack_limit_can_be_configured() {
+    let mut engine = Engine::new();
+    engine.set_stack_limit(64 * 1024);
+    let errors = engine.eval(&format!("{} int x := depth(1000);", RECURSION)).unwrap_err();
+    assert_eq!(errors[0].code, code::STACK_OVERFLOW);
+    assert!(errors[0].message.contains("64 KB"));
+}
+
+#[test]
+fn unassigned_argument_is_a_runtime_error() {
+    assert_eq!(error_codes("int x; print wrapping_add(x, 1);"), vec![code::UNASSIGNED_VARIABLE]);
+}
+
+#[test]
+fn unassigned_variable_cannot_be_updated() {
+    assert_eq!(error_codes("int x; x += 1;"), vec![code::UNASSIGNED_VARIABLE]);
+}
+
+#[test]
+fn unit_value_is_not_an_unassigned_variable() {
+    assert!(error_codes("Fn nothing() { } unit u := nothing(); print u;").is_empty());
+}
+
+#[test]
+fn unterminated_interpolation_points_at_its_brace() {
+    let errors = Engine::new().eval(r#"string s := "{x";"#).unwrap_err();
+    assert_eq!(errors[0].code, code::UNTERMINATED_INTERPOLATION);
+    assert_eq!(errors[0].span.unwrap().start.column, 14);
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use thatlang::diagnostic::code;
+use thatlang::Value;
+
+use common::{error_codes, run_for};
+
+#[test]
+fn recursive_function_returns_its_result() {
+    let source = "Fn fib(int n) int { if n < 2 { return n; } return fib(n - 1) + fib(n - 2); }
+                  int result := fib(10);";
+    assert_eq!(run_for(source, "result"), Value::IntegerValue(55));
+}
+
+#[test]
+fn function_can_be_called_before_its_declaration() {
+    let source = "int result := double(21);
+                  Fn double(int n) int { return n * 2; }";
+    assert_eq!(run_for(source, "result"), Value::IntegerValue(42));
+}
+
+#[test]
+fn functions_can_call_each_other() {
+    let source = "Fn is_even(int n) bool { if n == 0 { return true; } return is_odd(n - 1); }
+                  Fn is_odd(int n) bool { if n == 0 { return false; } return is_even(n - 1); }
+                  bool result := is_even(10);";
+    assert_eq!(run_for(source, "result"), Value::BooleanValue(true));
+}
+
+#[test]
+fn arguments_are_type_checked() {
+    assert_eq!(error_codes("Fn f(int n) int { return n; } int x := f(true);"), vec![code::MISMATCHED_TYPES]);
+    assert_eq!(error_codes("Fn f(int n) int { return n; } int x := f(1, 2);"), vec![code::WRONG_ARGUMENT_COUNT]);
+}
+
+#[test]
+fn function_must_return_on_every_path() {
+    assert_eq!(error_codes("Fn f(int n) int { if n > 0 { return n; } }"), vec![code::MISSING_RETURN]);
+}
+
+#[test]
+fn function_declared_twice_is_reported() {
+    assert_eq!(error_codes("Fn f() { } Fn f() { }"), vec![code::ALREADY_DECLARED]);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use thatlang::diagnostic::code;
+use thatlang::{Engine, Value};
+
+use common::{error_codes, run_for};
+
+fn evaluate(expression: &str) -> Value {
+    Engine::new().eval_expression(expression).unwrap()
+}
+
+#[test]
+fn prefix_operators_apply_to_their_operand() {
+    assert_eq!(run_for("int x := 3; x := -x;", "x"), Value::IntegerValue(-3));
+    assert_eq!(evaluate("- -1"), Value::IntegerValue(1));
+    assert_eq!(evaluate("!true"), Value::BooleanValue(false));
+    assert_eq!(evaluate("~5"), Value::IntegerValue(-6));
+}
+
+#[test]
+fn negation_binds_looser_than_power() {
+    assert_eq!(evaluate("-2 ** 2"), Value::IntegerValue(-4));
+    assert_eq!(evaluate("(-2) ** 2"), Value::IntegerValue(4));
+}
+
+#[test]
+fn prefix_operators_are_type_checked() {
+    assert_eq!(error_codes("bool b := !1;"), vec![code::INVALID_OPERANDS]);
+    assert_eq!(error_codes("float f := ~1.5;"), vec![code::INVALID_OPERANDS]);
+}
+
+#[test]
+fn unclosed_parenthesis_is_a_parse_error() {
+    let errors = Engine::new().eval("print 1 + (2;").unwrap_err();
+    assert_eq!(errors[0].code, code::UNCLOSED_PARENTHESIS);
+    assert_eq!(errors[0].span.unwrap().start.column, 11);
+    assert_eq!(error_codes("print (1 + 2;"), vec![code::UNCLOSED_PARENTHESIS]);
+}
+
+#[test]
+fn comparisons_evaluate_to_booleans() {
+    assert_eq!(evaluate("1 <= 1 && (2 >= 3) == false && 1 != 2"), Value::BooleanValue(true));
+    assert_eq!(evaluate("1.5 > 0.5"), Value::BooleanValue(true));
+}
+
+#[test]
+fn single_equal_comparison_is_deprecated() {
+    let mut engine = Engine::new();
+    engine.eval("bool same := 1 = 1;").unwrap();
+    assert_eq!(engine.get_global("same"), Some(Value::BooleanValue(true)));
+    assert_eq!(engine.warnings()[0].code, code::DEPRECATED_SYNTAX);
+}
+
+#[test]
+fn only_numbers_can_be_ordered() {
+    assert_eq!(error_codes(r#"bool b := "a" < "b";"#), vec![code::INVALID_OPERANDS]);
+    assert_eq!(error_codes("bool b := true >= false;"), vec![code::INVALID_OPERANDS]);
+    assert_eq!(evaluate(r#""a" != "b""#), Value::BooleanValue(true));
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    let source = "int[] xs := [1, 2];
+                  int i := 2;
+                  bool inside := i < xs.len() && xs[i] > 0;
+                  bool either := i >= xs.len() || xs[i] > 0;";
+    assert_eq!(run_for(source, "inside"), Value::BooleanValue(false));
+    assert_eq!(run_for(source, "either"), Value::BooleanValue(true));
+}
+
+#[test]
+fn logical_and_bitwise_operators_do_not_mix_types() {
+    assert_eq!(error_codes("int x := 1 && 2;"), vec![code::INVALID_OPERANDS]);
+    assert_eq!(error_codes("bool b := true & false;"), vec![code::INVALID_OPERANDS]);
+    assert_eq!(evaluate("6 & 3 | 8"), Value::IntegerValue(10));
+}
+
+#[test]
+fn arithmetic_operators_follow_their_precedence() {
+    assert_eq!(evaluate("1 + 2 * 3 ** 2"), Value::IntegerValue(19));
+    assert_eq!(evaluate("7 / 2 + -7 % 3"), Value::IntegerValue(2));
+    assert_eq!(evaluate("1 << 4 | -16 >> 2"), Value::IntegerValue(-4));
+    assert_eq!(evaluate("6 ^ 3"), Value::IntegerValue(5));
+}
+
+#[test]
+fn invalid_operands_are_runtime_errors() {
+    assert_eq!(error_codes("int zero := 0; int x := 5 % zero;"), vec![code::DIVISION_BY_ZERO]);
+    assert_eq!(error_codes("int bits := 40; int x := 1 << bits;"), vec![code::INVALID_SHIFT]);
+    assert_eq!(error_codes("int e := -1; int x := 2 ** e;"), vec![code::NEGATIVE_EXPONENT]);
+}
+
+#[test]
+fn compound_assignment_updates_the_target() {
+    let source = r#"int x := 10; x -= 3; x *= 2; x %= 5; x <<= 2;
+                    string s := "a"; s += "b";
+                    int[] xs := [1, 2]; xs[1] **= 3;"#;
+    assert_eq!(run_for(source, "x"), Value::IntegerValue(16));
+    assert_eq!(run_for(source, "s"), Value::StringValue("ab".to_string()));
+    assert_eq!(run_for(source, "xs"), Value::ArrayValue(vec![Value::IntegerValue(1), Value::IntegerValue(8)]));
+}
+
+#[test]
+fn compound_assignment_is_type_checked() {
+    assert_eq!(error_codes("y += 1;"), vec![code::UNDECLARED_VARIABLE]);
+    assert_eq!(error_codes("bool b := true; b += false;"), vec![code::INVALID_OPERANDS]);
+}
@@ -0,0 +1,37 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+//Feeds `input` to a REPL session and returns what it printed, without the prompts
+fn session(input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_thatlang"))
+        .args(["repl", "--color=never"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Unable to start thatlang");
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8_lossy(&output.stdout).replace(">> ", "").replace(".. ", "")
+}
+
+#[test]
+fn variables_persist_between_inputs() {
+    assert_eq!(session("int x := 3;\nprint x * 2;\n"), "6\n");
+}
+
+#[test]
+fn bare_expressions_print_their_value() {
+    assert_eq!(session("int x := 3;\nx + 1\n"), "4\n");
+}
+
+#[test]
+fn unclosed_blocks_continue_on_the_next_line() {
+    assert_eq!(session("if true {\nprint 1;\n}\n"), "1\n");
+}
+
+#[test]
+fn meta_commands_inspect_and_reset_the_session() {
+    assert_eq!(session("int x := 3;\n:type x > 1\n:vars\n"), "Boolean\nx: Integer = 3\n");
+    assert_eq!(session("int x := 3;\n:reset\n:vars\n"), "");
+}
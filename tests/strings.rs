@@ -0,0 +1,65 @@
+mod common;
+
+use thatlang::diagnostic::code;
+use thatlang::Value;
+
+use common::{error_codes, run_for};
+
+fn string(value: &str) -> Value {
+    Value::StringValue(value.to_string())
+}
+
+#[test]
+fn escapes_are_decoded() {
+    assert_eq!(run_for(r#"string s := "a\tb\n\"c\" \u{e9}";"#, "s"), string("a\tb\n\"c\" é"));
+}
+
+#[test]
+fn strings_are_concatenated_and_compared() {
+    assert_eq!(run_for(r#"string s := "foo" + "bar";"#, "s"), string("foobar"));
+    assert_eq!(run_for(r#"bool same := "ab" + "c" == "abc";"#, "same"), Value::BooleanValue(true));
+}
+
+#[test]
+fn strings_are_measured_and_indexed_by_character() {
+    let source = r#"string s := "héllo";
+                    int length := s.len();
+                    string second := s[1];
+                    string middle := substring(s, 1, 4);"#;
+    assert_eq!(run_for(source, "length"), Value::IntegerValue(5));
+    assert_eq!(run_for(source, "second"), string("é"));
+    assert_eq!(run_for(source, "middle"), string("éll"));
+}
+
+#[test]
+fn numbers_are_converted_to_and_from_strings() {
+    let source = r#"int n := parse_int(" 42 ") + 1;
+                    float f := parse_float("2.5");
+                    string s := int_to_string(n) + float_to_string(f);"#;
+    assert_eq!(run_for(source, "s"), string("432.5"));
+}
+
+#[test]
+fn invalid_conversion_is_a_runtime_error() {
+    assert_eq!(error_codes(r#"int n := parse_int("four");"#), vec![code::RUNTIME_FAILURE]);
+    assert_eq!(error_codes(r#"string s := substring("abc", 2, 5);"#), vec![code::RUNTIME_FAILURE]);
+}
+
+#[test]
+fn unassigned_string_is_a_runtime_error() {
+    assert_eq!(error_codes("string s; print s.len();"), vec![code::UNASSIGNED_VARIABLE]);
+    assert_eq!(error_codes("float f; print float_to_string(f);"), vec![code::UNASSIGNED_VARIABLE]);
+}
+
+#[test]
+fn expressions_are_interpolated() {
+    let source = r#"int i := 3; float half := 1.5; string s := "fib({i}) = {i * 2 - 1}, half {half}, \{i}";"#;
+    assert_eq!(run_for(source, "s"), string("fib(3) = 5, half 1.5, {i}"));
+}
+
+#[test]
+fn invalid_interpolations_are_rejected() {
+    assert_eq!(error_codes(r#"string s := "empty {}";"#), vec![code::INVALID_INTERPOLATION]);
+    assert_eq!(error_codes(r#"string s := "open {1 + 2"#), vec![code::UNTERMINATED_INTERPOLATION]);
+    assert_eq!(error_codes(r#"Fn nothing() { } string s := "{nothing()}";"#), vec![code::NOT_PRINTABLE]);
+}
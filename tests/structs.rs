@@ -0,0 +1,45 @@
+mod common;
+
+use thatlang::diagnostic::code;
+use thatlang::Value;
+
+use common::{error_codes, run_for};
+
+const POINT: &str = "struct Point { int x, int y, } struct Line { Point from, Point to, }";
+
+#[test]
+fn fields_are_read_and_assigned() {
+    let source = format!("{} Line l := Line {{ to: Point {{ y: 4, x: 3 }}, from: Point {{ x: 0, y: 0 }} }};
+                          l.to.x := 5;
+                          l.from.y += 1;
+                          int total := l.to.x + l.to.y + l.from.y;", POINT);
+    assert_eq!(run_for(&source, "total"), Value::IntegerValue(10));
+}
+
+#[test]
+fn struct_is_copied_on_assignment() {
+    let source = format!("{} Point p := Point {{ x: 1, y: 2 }}; Point q := p; q.x := 9;", POINT);
+    assert_eq!(run_for(&source, "p"), Value::StructValue("Point".to_string(), vec![
+        ("x".to_string(), Value::IntegerValue(1)),
+        ("y".to_string(), Value::IntegerValue(2)),
+    ]));
+}
+
+#[test]
+fn literals_and_fields_are_checked_against_the_declaration() {
+    assert_eq!(error_codes("Shape s;"), vec![code::UNKNOWN_TYPE]);
+    assert_eq!(error_codes(&format!("{} Point p := Point {{ x: 1 }};", POINT)), vec![code::MISSING_FIELD]);
+    assert_eq!(error_codes(&format!("{} Point p := Point {{ x: 1, y: 2 }}; print p.z;", POINT)),
+               vec![code::UNKNOWN_FIELD]);
+    assert_eq!(error_codes(&format!("{} Point p := Point {{ x: 1, y: true }};", POINT)), vec![code::MISMATCHED_TYPES]);
+}
+
+#[test]
+fn unassigned_struct_is_a_runtime_error() {
+    assert_eq!(error_codes(&format!("{} Point p; p.x := 1;", POINT)), vec![code::UNASSIGNED_VARIABLE]);
+}
+
+#[test]
+fn struct_must_declare_a_field() {
+    assert_eq!(error_codes("struct Empty {}"), vec![code::EMPTY_STRUCT]);
+}